@@ -0,0 +1,272 @@
+use crate::config::Config;
+use crate::error::Error;
+use crate::plugins::Plugins;
+use crate::protocol::Protocol;
+use serde_json::json;
+
+/// Dry-run report of mpv-handler link
+struct Report {
+    scheme: &'static str,
+    plugin: &'static str,
+    url: String,
+    playlist: Vec<String>,
+    params: Vec<(&'static str, String)>,
+    binaries: Vec<String>,
+    argv: Vec<String>,
+    resolve: bool,
+    envs: Vec<(&'static str, Option<String>)>,
+    ipc_server: Option<(String, bool)>,
+}
+
+/// Print what the given link would do, without running player
+///
+/// The link is parsed the same as opened by browser (see `Protocol::parse_arg`).
+/// If the video URL is resolved by yt-dlp before playing, the printed arguments
+/// are before resolving, without the resolved URL and its options.
+///
+/// ```
+/// mpv-handler decode [--json] <LINK>
+/// mpv-handler --dry-run [--json] <LINK>
+/// ```
+pub fn exec(args: &[String]) -> Result<(), Error> {
+    let mut json = false;
+    let mut link: Option<&str> = None;
+
+    for arg in args {
+        match arg.as_str() {
+            "--json" => json = true,
+            v if v.starts_with("--") || link.is_some() => {
+                return Err(Error::IncorrectArgument(v.to_string()));
+            }
+            v => link = Some(v),
+        }
+    }
+
+    let link = link.ok_or(Error::MissingArgument("<LINK>".to_string()))?;
+    let config = Config::load()?;
+    let mut proto = Protocol::parse_arg(link, &config)?;
+
+    // Apply default parameters of matched site rule
+    if let Some(site) = config.site(&proto.url) {
+        proto.apply_site(site)?;
+    }
+
+    let report = report(&proto, &config)?;
+
+    match json {
+        true => println!("{}", report.json()),
+        false => print!("{}", report.text()),
+    }
+
+    Ok(())
+}
+
+/// Returns dry-run report of `proto`, using the same options as plugins
+fn report(proto: &Protocol, config: &Config) -> Result<Report, Error> {
+    let mut binaries: Vec<String> = Vec::new();
+    let mut options: Vec<String>;
+    let mut ipc_server: Option<(String, bool)> = None;
+    let mut resolve = false;
+
+    match proto.plugin {
+        Plugins::Play | Plugins::Queue => {
+            let player = crate::players::player(config, &proto.url)?;
+
+            binaries.push(player.binary(config)?);
+            match player.ytdl() && !config.resolve {
+                true => binaries.extend(config.ytdl.clone()),
+                // The video URL is resolved by yt-dlp before playing
+                false => {
+                    binaries.push(crate::plugins::download::binary(config)?);
+                    resolve = proto.playlist.is_empty() || !player.ytdl();
+                }
+            }
+            options = player.options(proto, config)?;
+
+            // Same as plugin play, start a new player with IPC server if nobody answers
+            if player.name() == "mpv"
+                && let Some(v) = &config.ipc_server
+            {
+                let running = crate::ipc::Client::connect(v).is_some();
+                if !running {
+                    options.push(crate::players::mpv::ipc_server(v));
+                }
+                ipc_server = Some((v.clone(), running));
+            }
+
+            if !ipc_server.as_ref().is_some_and(|(_, running)| *running) {
+                options.extend(player.playlist_options(proto));
+            }
+        }
+        Plugins::Download => {
+            binaries.push(crate::plugins::download::binary(config)?);
+            options = crate::plugins::download::options(proto, config)?;
+        }
+    }
+
+    let mut argv: Vec<String> = vec![binaries[0].clone()];
+    argv.append(&mut options);
+    argv.push("--".to_string());
+    argv.extend(proto.urls().iter().map(|v| v.to_string()));
+
+    Ok(Report {
+        scheme: proto.scheme.name(),
+        plugin: proto.plugin.name(),
+        url: proto.url.clone(),
+        playlist: proto.playlist.clone(),
+        params: proto.params(),
+        binaries,
+        argv,
+        resolve,
+        envs: crate::plugins::envs(proto, config),
+        ipc_server,
+    })
+}
+
+impl Report {
+    /// Returns report as plain text
+    fn text(&self) -> String {
+        let mut text = String::new();
+
+        text.push_str(&format!("Scheme: {}\n", self.scheme));
+        text.push_str(&format!("Plugin: {}\n", self.plugin));
+        text.push_str(&format!("URL: {}\n", self.url));
+
+        if !self.playlist.is_empty() {
+            text.push_str("Playlist:\n");
+            for v in &self.playlist {
+                text.push_str(&format!("    {v}\n"));
+            }
+        }
+
+        if !self.params.is_empty() {
+            text.push_str("Parameters:\n");
+            for (k, v) in &self.params {
+                text.push_str(&format!("    {k}: {v}\n"));
+            }
+        }
+
+        text.push_str("Binaries:\n");
+        for v in &self.binaries {
+            text.push_str(&format!("    {v}\n"));
+        }
+
+        if let Some((v, running)) = &self.ipc_server {
+            let state = match running {
+                true => "running, the video will be loaded by JSON IPC",
+                false => "not running",
+            };
+            text.push_str(&format!("IPC server: {v} ({state})\n"));
+        }
+
+        match self.resolve {
+            true => text.push_str("Arguments (before resolving video URL by yt-dlp):\n"),
+            false => text.push_str("Arguments:\n"),
+        }
+        for v in &self.argv {
+            text.push_str(&format!("    {v}\n"));
+        }
+
+        if !self.envs.is_empty() {
+            text.push_str("Environment:\n");
+            for (k, v) in &self.envs {
+                match v {
+                    Some(v) => text.push_str(&format!("    {k}={v}\n")),
+                    None => text.push_str(&format!("    unset {k}\n")),
+                }
+            }
+        }
+
+        text
+    }
+
+    /// Returns report as JSON
+    fn json(&self) -> serde_json::Value {
+        let params: serde_json::Map<String, serde_json::Value> = self
+            .params
+            .iter()
+            .map(|(k, v)| (k.to_string(), json!(v)))
+            .collect();
+        let set: serde_json::Map<String, serde_json::Value> = self
+            .envs
+            .iter()
+            .filter_map(|(k, v)| v.as_ref().map(|v| (k.to_string(), json!(v))))
+            .collect();
+        let unset: Vec<&str> = self
+            .envs
+            .iter()
+            .filter(|(_, v)| v.is_none())
+            .map(|(k, _)| *k)
+            .collect();
+
+        json!({
+            "scheme": self.scheme,
+            "plugin": self.plugin,
+            "url": self.url,
+            "playlist": self.playlist,
+            "parameters": params,
+            "binaries": self.binaries,
+            "ipc_server": self.ipc_server.as_ref().map(|(v, running)| json!({
+                "path": v,
+                "running": running,
+            })),
+            "argv": self.argv,
+            "resolve": self.resolve,
+            "env": { "set": set, "unset": unset },
+        })
+    }
+}
+
+#[test]
+fn test_decode_report() {
+    let config: Config = toml::from_str(
+        r#"
+            mpv = "/usr/bin/mpv"
+            proxy = "http://example.com:8080"
+        "#,
+    )
+    .unwrap();
+    let proto = Protocol::parse(
+        "mpv-handler-debug://play/aHR0cHM6Ly93d3cueW91dHViZS5jb20vd2F0Y2g_dj1HZ2tuMmY1ZS1JVQ/?quality=1080p&v_title=VGl0bGU",
+        &config,
+    )
+    .unwrap();
+
+    let report = report(&proto, &config).unwrap();
+    assert_eq!(
+        report.argv,
+        [
+            "/usr/bin/mpv",
+            "--ytdl-raw-options-append=format-sort=res:1080",
+            "--title=Title",
+            "--",
+            "https://www.youtube.com/watch?v=Ggkn2f5e-IU",
+        ]
+    );
+
+    assert!(!report.resolve);
+
+    let text = report.text();
+    assert!(text.contains("Scheme: mpv-handler-debug\n"));
+    assert!(text.contains("Arguments:\n"));
+    assert!(text.contains("    v_title: Title\n"));
+    assert!(text.contains("    http_proxy=http://example.com:8080\n"));
+
+    let json = report.json();
+    assert_eq!(json["plugin"], "play");
+    assert_eq!(json["parameters"]["quality"], "1080p");
+    assert_eq!(json["env"]["set"]["HTTPS_PROXY"], "http://example.com:8080");
+    assert_eq!(json["argv"][0], "/usr/bin/mpv");
+
+    // The video URL is resolved in resolve mode
+    let config: Config = toml::from_str("resolve = true\nytdl = \"/usr/bin/yt-dlp\"").unwrap();
+    let resolved = self::report(&proto, &config).unwrap();
+    assert!(resolved.resolve);
+    assert_eq!(resolved.binaries[1], "/usr/bin/yt-dlp");
+    assert!(
+        resolved
+            .text()
+            .contains("Arguments (before resolving video URL by yt-dlp):\n")
+    );
+    assert_eq!(resolved.json()["resolve"], true);
+}
@@ -0,0 +1,588 @@
+use crate::config::Config;
+use crate::error::Error;
+use crate::protocol::Schemes;
+use std::path::Path;
+#[cfg(unix)]
+use std::path::PathBuf;
+
+/// Result of check
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Status {
+    Pass,
+    Warn,
+    Fail,
+}
+
+impl Status {
+    /// Returns the label of status
+    fn name(&self) -> &'static str {
+        match self {
+            Status::Pass => "PASS",
+            Status::Warn => "WARN",
+            Status::Fail => "FAIL",
+        }
+    }
+}
+
+/// Check of installation
+#[derive(Debug)]
+struct Check {
+    name: String,
+    status: Status,
+    message: String,
+}
+
+impl Check {
+    fn new<S: Into<String>>(name: &str, status: Status, message: S) -> Check {
+        Check {
+            name: name.to_string(),
+            status,
+            message: message.into(),
+        }
+    }
+}
+
+/// Diagnose the installation
+///
+/// ```
+/// mpv-handler doctor
+/// ```
+///
+/// Exit code: `0` all checks passed, `1` some checks failed, `2` only warnings.
+pub fn exec(args: &[String]) -> Result<(), Error> {
+    if let Some(v) = args.first() {
+        return Err(Error::IncorrectArgument(v.to_string()));
+    }
+
+    let checks = checks();
+
+    for check in &checks {
+        println!(
+            "[{}] {}: {}",
+            check.status.name(),
+            check.name,
+            check.message
+        );
+    }
+
+    let count = |s: Status| checks.iter().filter(|v| v.status == s).count();
+    println!(
+        "\n{} passed, {} warnings, {} failed",
+        count(Status::Pass),
+        count(Status::Warn),
+        count(Status::Fail)
+    );
+
+    result(&checks)
+}
+
+/// Returns all checks
+fn checks() -> Vec<Check> {
+    let mut checks: Vec<Check> = Vec::new();
+    let dir = crate::config::get_config_dir();
+
+    // Config directory and config file
+    let config = match &dir {
+        Some(dir) => {
+            checks.push(config_dir_check(dir));
+
+            let (check, config) = config_check(&dir.join("config.toml"), Config::load);
+            checks.push(check);
+            config
+        }
+        None => {
+            checks.push(Check::new(
+                "Config directory",
+                Status::Fail,
+                "failed to get config directory",
+            ));
+            Config::default()
+        }
+    };
+
+    // Binaries
+    // Players of config and site rules
+    let mut names: Vec<&str> = vec![config.player.as_deref().unwrap_or("mpv")];
+    for site in &config.site {
+        if let Some(v) = &site.player
+            && !names.contains(&v.as_str())
+        {
+            names.push(v);
+        }
+    }
+    for player in names.into_iter().filter_map(crate::players::from_name) {
+        checks.push(binary_check(
+            player.name(),
+            player.binary(&config),
+            Status::Fail,
+        ));
+    }
+    checks.push(binary_check(
+        "yt-dlp",
+        crate::plugins::download::binary(&config),
+        Status::Warn,
+    ));
+
+    // Cookies directory
+    if let Some(dir) = &dir {
+        checks.push(cookies_check(&dir.join("cookies")));
+    }
+
+    // Scheme handlers
+    for scheme in [Schemes::MpvHandler, Schemes::MpvHandlerDebug] {
+        #[cfg(unix)]
+        checks.push(scheme_check(&scheme, &data_dirs(), &mimeapps_path()));
+        #[cfg(windows)]
+        checks.push(scheme_check(&scheme));
+    }
+
+    // Library path environment variables
+    #[cfg(unix)]
+    for name in ["LD_PRELOAD", "LD_LIBRARY_PATH"] {
+        checks.push(env_check(name, std::env::var_os(name)));
+    }
+
+    checks
+}
+
+/// Returns exit result of checks
+fn result(checks: &[Check]) -> Result<(), Error> {
+    let count = |s: Status| checks.iter().filter(|v| v.status == s).count();
+
+    match (count(Status::Fail), count(Status::Warn)) {
+        (0, 0) => Ok(()),
+        (0, n) => Err(Error::DoctorWarned(n)),
+        (n, _) => Err(Error::DoctorFailed(n)),
+    }
+}
+
+/// Check config directory
+fn config_dir_check(dir: &Path) -> Check {
+    let name = "Config directory";
+
+    match dir.is_dir() {
+        true => Check::new(name, Status::Pass, dir.display().to_string()),
+        false => Check::new(
+            name,
+            Status::Pass,
+            format!("{} (not exists, default config is used)", dir.display()),
+        ),
+    }
+}
+
+/// Check config file, returns the loaded config (or default config if failed)
+fn config_check<F>(path: &Path, load: F) -> (Check, Config)
+where
+    F: Fn() -> Result<Config, Error>,
+{
+    let name = "Config file";
+
+    if !path.exists() {
+        let message = format!("{} (not exists, default config is used)", path.display());
+        return (Check::new(name, Status::Pass, message), Config::default());
+    }
+
+    match load() {
+        Ok(config) => (
+            Check::new(name, Status::Pass, path.display().to_string()),
+            config,
+        ),
+        Err(e) => (
+            Check::new(name, Status::Fail, format!("{} ({e})", path.display())),
+            Config::default(),
+        ),
+    }
+}
+
+/// Check binary path and its version
+fn binary_check(name: &str, binary: Result<String, Error>, missing: Status) -> Check {
+    let path = match binary {
+        Ok(v) => v,
+        Err(e) => return Check::new(name, missing, e.to_string()),
+    };
+
+    if !Path::new(&path).is_absolute() || !Path::new(&path).is_file() {
+        return Check::new(name, missing, format!("\"{path}\" not found"));
+    }
+
+    match version(&path) {
+        Ok(v) => Check::new(name, Status::Pass, format!("{path} ({v})")),
+        Err(e) => Check::new(name, Status::Fail, format!("{path} ({e})")),
+    }
+}
+
+/// Returns the first line of `--version` output
+fn version(binary: &str) -> Result<String, String> {
+    let output = std::process::Command::new(binary)
+        .arg("--version")
+        .stdin(std::process::Stdio::null())
+        .output()
+        .map_err(|e| e.to_string())?;
+
+    if !output.status.success() {
+        return Err(format!("--version exited with {}", output.status));
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    match stdout.lines().map(|v| v.trim()).find(|v| !v.is_empty()) {
+        Some(v) => Ok(v.to_string()),
+        None => Err("--version printed nothing".to_string()),
+    }
+}
+
+/// Check cookies directory
+fn cookies_check(dir: &Path) -> Check {
+    let name = "Cookies directory";
+
+    if dir.is_dir() {
+        let files = std::fs::read_dir(dir).map_or(0, |v| v.count());
+        return Check::new(
+            name,
+            Status::Pass,
+            format!("{} ({files} files)", dir.display()),
+        );
+    }
+
+    match dir.exists() {
+        true => Check::new(
+            name,
+            Status::Fail,
+            format!("{} is not a directory", dir.display()),
+        ),
+        false => Check::new(
+            name,
+            Status::Pass,
+            format!(
+                "{} (not exists, cookies parameter is ignored)",
+                dir.display()
+            ),
+        ),
+    }
+}
+
+/// Returns user and system data directories (`$XDG_DATA_HOME`, `$XDG_DATA_DIRS`)
+#[cfg(unix)]
+fn data_dirs() -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = dirs::data_dir().into_iter().collect();
+
+    match std::env::var_os("XDG_DATA_DIRS").filter(|v| !v.is_empty()) {
+        Some(v) => dirs.extend(std::env::split_paths(&v)),
+        None => dirs.extend(["/usr/local/share", "/usr/share"].map(PathBuf::from)),
+    }
+
+    dirs
+}
+
+/// Returns the path of user `mimeapps.list`
+#[cfg(unix)]
+fn mimeapps_path() -> PathBuf {
+    dirs::config_dir().unwrap_or_default().join("mimeapps.list")
+}
+
+/// Check desktop entry and default application of scheme
+#[cfg(unix)]
+fn scheme_check(scheme: &Schemes, data_dirs: &[PathBuf], mimeapps: &Path) -> Check {
+    use crate::commands::install::{default_application, desktop_name, mime_type};
+
+    let name = format!("Scheme handler {}", scheme.name());
+    let desktop = desktop_name(scheme);
+    let mime = mime_type(scheme);
+
+    // Desktop entry
+    let path = data_dirs
+        .iter()
+        .map(|v| v.join("applications").join(&desktop))
+        .find(|v| v.is_file());
+    let path = match path {
+        Some(v) => v,
+        None => return Check::new(&name, Status::Fail, format!("{desktop} not found")),
+    };
+    let entry = std::fs::read_to_string(&path).unwrap_or_default();
+
+    if !entry
+        .lines()
+        .any(|v| v.starts_with("MimeType=") && v.split(['=', ';']).any(|v| v == mime))
+    {
+        let message = format!("{} has no MimeType {mime}", path.display());
+        return Check::new(&name, Status::Fail, message);
+    }
+
+    match exec_binary(&entry) {
+        Some(v) if Path::new(&v).is_file() => {}
+        v => {
+            let message = format!(
+                "{} runs \"{}\", which is not found",
+                path.display(),
+                v.unwrap_or_default()
+            );
+            return Check::new(&name, Status::Fail, message);
+        }
+    }
+
+    // Default application
+    let list = std::fs::read_to_string(mimeapps).unwrap_or_default();
+
+    match default_application(&list, &mime) {
+        Some(v) if v == desktop => Check::new(&name, Status::Pass, path.display().to_string()),
+        Some(v) => {
+            let message = format!("default handler is {v} in {}", mimeapps.display());
+            Check::new(&name, Status::Fail, message)
+        }
+        None => {
+            let message = format!("{} (not default in {})", path.display(), mimeapps.display());
+            Check::new(&name, Status::Warn, message)
+        }
+    }
+}
+
+/// Returns the binary path of `Exec=` key in desktop entry, relative path is found in `PATH`
+#[cfg(unix)]
+fn exec_binary(entry: &str) -> Option<String> {
+    let exec = entry.lines().find_map(|v| v.strip_prefix("Exec="))?.trim();
+
+    let binary = match exec.strip_prefix('"') {
+        Some(v) => {
+            let mut binary = String::new();
+            let mut chars = v
+                .replace(r"\\", "\\")
+                .chars()
+                .collect::<Vec<char>>()
+                .into_iter();
+
+            while let Some(c) = chars.next() {
+                match c {
+                    '\\' => binary.extend(chars.next()),
+                    '"' => break,
+                    _ => binary.push(c),
+                }
+            }
+            binary.replace("%%", "%")
+        }
+        None => exec.split_whitespace().next()?.to_string(),
+    };
+
+    crate::config::realpath(&binary).ok()
+}
+
+/// Check registry of scheme
+#[cfg(windows)]
+fn scheme_check(scheme: &Schemes) -> Check {
+    let name = format!("Scheme handler {}", scheme.name());
+    let key = format!(
+        r"HKCU\Software\Classes\{}\shell\open\command",
+        scheme.name()
+    );
+
+    let output = std::process::Command::new("reg")
+        .args(["query", &key, "/ve"])
+        .output();
+
+    match output {
+        Ok(o) if o.status.success() => {
+            let stdout = String::from_utf8_lossy(&o.stdout);
+            let command = stdout
+                .lines()
+                .find_map(|v| v.split_once("REG_SZ").map(|(_, v)| v.trim().to_string()))
+                .unwrap_or_default();
+            Check::new(&name, Status::Pass, command)
+        }
+        _ => Check::new(&name, Status::Fail, format!("{key} not found")),
+    }
+}
+
+/// Check library path environment variable
+#[cfg(unix)]
+fn env_check(name: &str, value: Option<std::ffi::OsString>) -> Check {
+    match value.filter(|v| !v.is_empty()) {
+        Some(v) => Check::new(
+            name,
+            Status::Warn,
+            format!(
+                "{} (removed for player and downloader)",
+                v.to_string_lossy()
+            ),
+        ),
+        None => Check::new(name, Status::Pass, "not set"),
+    }
+}
+
+#[test]
+fn test_doctor_result() {
+    let pass = Check::new("a", Status::Pass, "");
+    let warn = Check::new("b", Status::Warn, "");
+    let fail = Check::new("c", Status::Fail, "");
+
+    assert!(result(&[]).is_ok());
+    assert!(matches!(result(&[pass, warn]), Err(Error::DoctorWarned(1))));
+
+    let warn = Check::new("b", Status::Warn, "");
+    assert!(matches!(result(&[warn, fail]), Err(Error::DoctorFailed(1))));
+}
+
+#[test]
+fn test_config_check() {
+    let dir = crate::testing::temp_path("doctor");
+    let path = dir.join("config.toml");
+
+    let (check, _) = config_check(&path, || unreachable!());
+    assert_eq!(check.status, Status::Pass);
+    assert!(check.message.contains("not exists"));
+
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(&path, "mpv = 1").unwrap();
+
+    let load = || Ok(toml::from_str(&std::fs::read_to_string(&path)?)?);
+    let (check, config) = config_check(&path, load);
+    assert_eq!(check.status, Status::Fail);
+    assert!(check.message.contains("Failed to decode"));
+    assert_eq!(config.mpv, None);
+
+    std::fs::write(&path, "mpv = \"/usr/bin/mpv\"").unwrap();
+    let (check, config) = config_check(&path, load);
+    assert_eq!(check.status, Status::Pass);
+    assert_eq!(config.mpv, Some("/usr/bin/mpv".to_string()));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn test_binary_check() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = crate::testing::temp_path("doctor-bin");
+    let mpv = dir.join("mpv");
+    let broken = dir.join("broken");
+
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        &mpv,
+        "#!/bin/sh\necho \"mpv 0.40.0 Copyright\"\necho more\n",
+    )
+    .unwrap();
+    std::fs::write(&broken, "#!/bin/sh\nexit 1\n").unwrap();
+    for p in [&mpv, &broken] {
+        std::fs::set_permissions(p, std::fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    let check = binary_check("mpv", Ok(mpv.display().to_string()), Status::Fail);
+    assert_eq!(check.status, Status::Pass);
+    assert_eq!(
+        check.message,
+        format!("{} (mpv 0.40.0 Copyright)", mpv.display())
+    );
+
+    let check = binary_check("mpv", Ok(broken.display().to_string()), Status::Fail);
+    assert_eq!(check.status, Status::Fail);
+
+    let check = binary_check("yt-dlp", Ok("yt-dlp".to_string()), Status::Warn);
+    assert_eq!(check.status, Status::Warn);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_cookies_check() {
+    let dir = crate::testing::temp_path("doctor-cookies");
+
+    assert!(cookies_check(&dir).message.contains("not exists"));
+
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("www.youtube.com.txt"), "").unwrap();
+
+    let check = cookies_check(&dir);
+    assert_eq!(check.status, Status::Pass);
+    assert!(check.message.ends_with("(1 files)"));
+
+    let check = cookies_check(&dir.join("www.youtube.com.txt"));
+    assert_eq!(check.status, Status::Fail);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn test_scheme_check() {
+    let root = crate::testing::temp_path("doctor-scheme");
+    let data = root.join("data");
+    let mimeapps = root.join("mimeapps.list");
+    let applications = data.join("applications");
+    let scheme = Schemes::MpvHandler;
+    let dirs = [data];
+
+    let check = scheme_check(&scheme, &dirs, &mimeapps);
+    assert_eq!(check.status, Status::Fail);
+    assert!(check.message.contains("mpv-handler.desktop not found"));
+
+    std::fs::create_dir_all(&applications).unwrap();
+    std::fs::write(
+        applications.join("mpv-handler.desktop"),
+        "[Desktop Entry]\nExec=\"/nonexistent/mpv-handler\" %u\nMimeType=x-scheme-handler/mpv-handler;\n",
+    )
+    .unwrap();
+
+    let check = scheme_check(&scheme, &dirs, &mimeapps);
+    assert_eq!(check.status, Status::Fail);
+    assert!(check.message.contains("\"/nonexistent/mpv-handler\""));
+
+    std::fs::write(
+        applications.join("mpv-handler.desktop"),
+        "[Desktop Entry]\nExec=sh %u\nMimeType=x-scheme-handler/mpv-handler;\n",
+    )
+    .unwrap();
+
+    let check = scheme_check(&scheme, &dirs, &mimeapps);
+    assert_eq!(check.status, Status::Warn);
+
+    std::fs::write(
+        &mimeapps,
+        "[Default Applications]\nx-scheme-handler/mpv-handler=other.desktop;\n",
+    )
+    .unwrap();
+    let check = scheme_check(&scheme, &dirs, &mimeapps);
+    assert_eq!(check.status, Status::Fail);
+    assert!(check.message.contains("other.desktop"));
+
+    std::fs::write(
+        &mimeapps,
+        "[Default Applications]\nx-scheme-handler/mpv-handler=mpv-handler.desktop;\n",
+    )
+    .unwrap();
+    let check = scheme_check(&scheme, &dirs, &mimeapps);
+    assert_eq!(check.status, Status::Pass);
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn test_exec_binary() {
+    assert_eq!(
+        exec_binary("Exec=\"/opt/a b/mpv-handler\" %u\n"),
+        Some("/opt/a b/mpv-handler".to_string())
+    );
+    assert_eq!(
+        exec_binary("Exec=\"/opt/\\\\$x%%/m\\\\\"h\" %u\n"),
+        Some("/opt/$x%/m\"h".to_string())
+    );
+    assert_eq!(
+        exec_binary("Exec=/usr/bin/mpv-handler %u\n"),
+        Some("/usr/bin/mpv-handler".to_string())
+    );
+    assert_eq!(exec_binary("Name=mpv-handler\n"), None);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_env_check() {
+    assert_eq!(env_check("LD_PRELOAD", None).status, Status::Pass);
+    assert_eq!(
+        env_check("LD_PRELOAD", Some("".into())).status,
+        Status::Pass
+    );
+
+    let check = env_check("LD_PRELOAD", Some("/usr/lib/libfoo.so".into()));
+    assert_eq!(check.status, Status::Warn);
+    assert!(check.message.starts_with("/usr/lib/libfoo.so"));
+}
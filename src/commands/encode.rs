@@ -0,0 +1,206 @@
+use crate::commands::value;
+use crate::config::Config;
+use crate::error::Error;
+use crate::plugins::Plugins;
+use crate::protocol::{Protocol, Schemes};
+
+/// Print mpv-handler link of the given URLs and options
+///
+/// Multiple URLs are encoded as a playlist, and the link is signed by
+/// `Config.sign_secret` with option `--sign`.
+///
+/// ```
+/// mpv-handler encode [OPTIONS] <URL>...
+/// ```
+pub fn exec(args: &[String]) -> Result<(), Error> {
+    // Config is only required by signing
+    let secret = match args.iter().any(|v| v == "--sign") {
+        true => Config::load()?.sign_secret,
+        false => None,
+    };
+
+    println!("{}", link(args, secret.as_deref())?);
+    Ok(())
+}
+
+/// Returns mpv-handler link of the given URLs and options, `secret` is used by `--sign`
+fn link(args: &[String], secret: Option<&str>) -> Result<String, Error> {
+    let mut args = args.iter();
+    let mut scheme = Schemes::MpvHandler;
+    let mut plugin = Plugins::Play;
+    let mut urls: Vec<String> = Vec::new();
+    let mut cookies: Option<&str> = None;
+    let mut profile: Option<&str> = None;
+    let mut quality = None;
+    let mut v_codec = None;
+    let mut v_title: Option<String> = None;
+    let mut subfile: Option<String> = None;
+    let mut startat = None;
+    let mut referrer: Option<String> = None;
+    let mut playlist_start = None;
+    let mut shuffle = false;
+    let mut expires: Option<u64> = None;
+    let mut sign = false;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--debug" => scheme = Schemes::MpvHandlerDebug,
+            "--plugin" => {
+                let v = value(&mut args, arg)?;
+                plugin = Plugins::from_name(v).ok_or(Error::IncorrectArgument(v.to_string()))?;
+            }
+            "--cookies" => cookies = Some(value(&mut args, arg)?),
+            "--profile" => profile = Some(crate::params::profile(value(&mut args, arg)?)?),
+            "--quality" => quality = Some(value(&mut args, arg)?.parse()?),
+            "--v-codec" => v_codec = Some(value(&mut args, arg)?.parse()?),
+            "--title" => v_title = Some(value(&mut args, arg)?.to_string()),
+            "--subfile" => subfile = Some(value(&mut args, arg)?.to_string()),
+            "--startat" => startat = Some(value(&mut args, arg)?.parse()?),
+            "--referrer" => referrer = Some(value(&mut args, arg)?.to_string()),
+            "--playlist-start" => {
+                let v = value(&mut args, arg)?;
+                playlist_start = Some(
+                    v.parse()
+                        .map_err(|_| Error::IncorrectPlaylist(v.to_string()))?,
+                );
+            }
+            "--shuffle" => shuffle = true,
+            "--expires" => {
+                let v = value(&mut args, arg)?;
+                expires = Some(
+                    v.parse()
+                        .map_err(|_| Error::IncorrectArgument(v.to_string()))?,
+                );
+            }
+            "--sign" => sign = true,
+            v if v.starts_with("--") => return Err(Error::IncorrectArgument(v.to_string())),
+            v => urls.push(v.to_string()),
+        }
+    }
+
+    if urls.is_empty() {
+        return Err(Error::MissingArgument("<URL>".to_string()));
+    }
+
+    let proto = Protocol {
+        scheme,
+        plugin,
+        url: urls.remove(0),
+        playlist: urls,
+        cookies,
+        profile,
+        quality,
+        v_codec,
+        v_title,
+        subfile,
+        startat,
+        referrer,
+        playlist_start,
+        shuffle,
+    };
+    let mut link = proto.encode();
+
+    // Expiration time of link, seconds from now
+    if let Some(v) = expires {
+        let exp = crate::protocol::unix_time() + v;
+        crate::protocol::push_param(&mut link, "exp", &exp.to_string());
+    }
+
+    if sign {
+        let secret = secret.ok_or(Error::MissingConfig("sign_secret".to_string()))?;
+        link = crate::protocol::sign(&link, secret);
+    }
+
+    // Check the video URLs, subfile, playlist parameters and signature
+    let config = Config {
+        sign_secret: secret.filter(|_| sign).map(str::to_string),
+        ..Default::default()
+    };
+    Protocol::parse(&link, &config)?;
+
+    Ok(link)
+}
+
+#[test]
+fn test_encode_link() {
+    let args = crate::testing::strings;
+
+    let l = link(
+        &args(&[
+            "--quality",
+            "1080p",
+            "--title",
+            "Title",
+            "https://www.youtube.com/watch?v=Ggkn2f5e-IU",
+            "--subfile",
+            "http://example.com/en.ass",
+        ]),
+        None,
+    )
+    .unwrap();
+    assert_eq!(
+        l,
+        "mpv-handler://play/aHR0cHM6Ly93d3cueW91dHViZS5jb20vd2F0Y2g_dj1HZ2tuMmY1ZS1JVQ/?quality=1080p&v_title=VGl0bGU&subfile=aHR0cDovL2V4YW1wbGUuY29tL2VuLmFzcw"
+    );
+
+    let l = link(
+        &args(&["--debug", "--plugin", "download", "https://example.com/"]),
+        None,
+    )
+    .unwrap();
+    assert_eq!(
+        l,
+        "mpv-handler-debug://download/aHR0cHM6Ly9leGFtcGxlLmNvbS8/"
+    );
+
+    assert!(matches!(
+        link(&args(&["file:///etc/passwd"]), None),
+        Err(Error::DangerousVideoProtocol(_))
+    ));
+    assert!(matches!(
+        link(&args(&["--quality"]), None),
+        Err(Error::MissingArgument(_))
+    ));
+    assert!(matches!(
+        link(&args(&["--unknown", "https://example.com/"]), None),
+        Err(Error::IncorrectArgument(_))
+    ));
+    assert!(matches!(link(&[], None), Err(Error::MissingArgument(_))));
+
+    // Playlist
+    let l = link(
+        &args(&[
+            "--shuffle",
+            "--playlist-start",
+            "1",
+            "https://example.com/",
+            "https://example.org/",
+        ]),
+        None,
+    )
+    .unwrap();
+    assert_eq!(
+        l,
+        "mpv-handler://play/aHR0cHM6Ly9leGFtcGxlLmNvbS8/aHR0cHM6Ly9leGFtcGxlLm9yZy8/?playlist_start=1&shuffle=true"
+    );
+    assert!(matches!(
+        link(
+            &args(&["--playlist-start", "1", "https://example.com/"]),
+            None
+        ),
+        Err(Error::IncorrectPlaylist(_))
+    ));
+
+    // Signed link
+    let l = link(
+        &args(&["--sign", "--expires", "60", "https://example.com/"]),
+        Some("secret"),
+    )
+    .unwrap();
+    assert!(l.starts_with("mpv-handler://play/aHR0cHM6Ly9leGFtcGxlLmNvbS8/?exp="));
+    assert!(l.contains("&sig="));
+    assert!(matches!(
+        link(&args(&["--sign", "https://example.com/"]), None),
+        Err(Error::MissingConfig(_))
+    ));
+}
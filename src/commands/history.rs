@@ -0,0 +1,187 @@
+use crate::config::Config;
+use crate::error::Error;
+use crate::history::Entry;
+use crate::protocol::Protocol;
+
+/// Default number of listed entries
+const DEFAULT_LIMIT: usize = 20;
+
+/// List, search, replay or clear playback history
+///
+/// ```
+/// mpv-handler history [list] [--limit <N>]
+/// mpv-handler history search <TEXT>
+/// mpv-handler history replay <ID>
+/// mpv-handler history clear
+/// ```
+pub fn exec(args: &[String]) -> Result<(), Error> {
+    let path = crate::history::path().ok_or(Error::MissingConfig("state_dir".to_string()))?;
+    let mut args = args.iter();
+
+    match args.next().map(String::as_str) {
+        None => list(&crate::history::load(&path)?, DEFAULT_LIMIT),
+        Some("list") => {
+            let mut limit = DEFAULT_LIMIT;
+
+            while let Some(arg) = args.next() {
+                match arg.as_str() {
+                    "--limit" => {
+                        let v = crate::commands::value(&mut args, arg)?;
+                        limit = v
+                            .parse()
+                            .map_err(|_| Error::IncorrectArgument(v.to_string()))?;
+                    }
+                    v => return Err(Error::IncorrectArgument(v.to_string())),
+                }
+            }
+
+            list(&crate::history::load(&path)?, limit)
+        }
+        Some("search") => {
+            let text = one(&mut args, "<TEXT>")?;
+            let entries: Vec<Entry> = crate::history::load(&path)?
+                .into_iter()
+                .filter(|v| v.matches(text))
+                .collect();
+
+            list(&entries, entries.len())
+        }
+        Some("replay") => {
+            let id = one(&mut args, "<ID>")?;
+            let id: u64 = id
+                .parse()
+                .map_err(|_| Error::IncorrectArgument(id.to_string()))?;
+            let entry = crate::history::load(&path)?
+                .into_iter()
+                .find(|v| v.id == id)
+                .ok_or(Error::MissingHistoryEntry(id))?;
+
+            replay(&entry)
+        }
+        Some("clear") => {
+            if let Some(v) = args.next() {
+                return Err(Error::IncorrectArgument(v.to_string()));
+            }
+
+            crate::history::clear(&path)?;
+            println!("History cleared");
+            Ok(())
+        }
+        Some(v) => Err(Error::IncorrectArgument(v.to_string())),
+    }
+}
+
+/// Returns the only argument, e.g. `search <TEXT>`
+fn one<'a>(args: &mut std::slice::Iter<'a, String>, name: &str) -> Result<&'a str, Error> {
+    let v = args
+        .next()
+        .ok_or(Error::MissingArgument(name.to_string()))?;
+
+    match args.next() {
+        Some(v) => Err(Error::IncorrectArgument(v.to_string())),
+        None => Ok(v),
+    }
+}
+
+/// Print the latest `limit` entries, from oldest to newest
+fn list(entries: &[Entry], limit: usize) -> Result<(), Error> {
+    for entry in &entries[entries.len().saturating_sub(limit)..] {
+        println!("{}", line(entry));
+    }
+
+    Ok(())
+}
+
+/// Returns the line of listed entry
+fn line(entry: &Entry) -> String {
+    let time = entry
+        .time
+        .get(..19)
+        .unwrap_or(&entry.time)
+        .replace('T', " ");
+
+    match &entry.title {
+        Some(title) => format!(
+            "{:>4}  {time}  [{}]  {title} <{}>",
+            entry.id, entry.status, entry.url
+        ),
+        None => format!("{:>4}  {time}  [{}]  {}", entry.id, entry.status, entry.url),
+    }
+}
+
+/// Rebuild `Protocol` by the link of `entry`, and run the plugin again
+///
+/// The link is checked by current config, as a link opened by browser.
+/// The entry with redacted secrets is refused, its URL is broken.
+fn replay(entry: &Entry) -> Result<(), Error> {
+    if entry.redacted {
+        return Err(Error::RedactedHistoryEntry(entry.id));
+    }
+
+    let config = Config::load()?;
+
+    crate::log::init(&config)?;
+    crate::log::info(
+        "replay",
+        serde_json::json!({ "id": entry.id, "arg": crate::log::redact_arg(&entry.link) }),
+    );
+
+    // The link of history isn't signed, it's replayed by user
+    let link = match &config.sign_secret {
+        Some(v) => crate::protocol::sign(&entry.link, v),
+        None => entry.link.clone(),
+    };
+    let mut proto = Protocol::parse(&link, &config)?;
+
+    // Apply default parameters of matched site rule
+    if let Some(site) = config.site(&proto.url) {
+        proto.apply_site(site)?;
+    }
+
+    crate::plugins::exec(&proto, &config)
+}
+
+#[test]
+fn test_line() {
+    let mut entry = Entry {
+        id: 7,
+        time: "2024-01-02T03:04:05.678Z".to_string(),
+        url: "https://www.youtube.com/watch?v=Ggkn2f5e-IU".to_string(),
+        title: None,
+        site: Some("www.youtube.com".to_string()),
+        status: 0,
+        link: String::new(),
+        redacted: false,
+    };
+
+    assert_eq!(
+        line(&entry),
+        "   7  2024-01-02 03:04:05  [0]  https://www.youtube.com/watch?v=Ggkn2f5e-IU"
+    );
+
+    entry.title = Some("Marble Soda".to_string());
+    assert_eq!(
+        line(&entry),
+        "   7  2024-01-02 03:04:05  [0]  Marble Soda <https://www.youtube.com/watch?v=Ggkn2f5e-IU>"
+    );
+}
+
+#[test]
+fn test_replay_redacted() {
+    let entry = Entry {
+        id: 3,
+        time: "2024-01-02T03:04:05.678Z".to_string(),
+        url: "https://example.com/v.m3u8?token=REDACTED".to_string(),
+        title: None,
+        site: Some("example.com".to_string()),
+        status: 0,
+        link: "mpv-handler://play/aHR0cHM6Ly9leGFtcGxlLmNvbS92Lm0zdTg_dG9rZW49UkVEQUNURUQ/"
+            .to_string(),
+        redacted: true,
+    };
+
+    assert!(matches!(
+        replay(&entry),
+        Err(Error::RedactedHistoryEntry(3))
+    ));
+}
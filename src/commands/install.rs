@@ -0,0 +1,444 @@
+use crate::config::Config;
+use crate::error::Error;
+use crate::protocol::Schemes;
+use std::path::{Path, PathBuf};
+
+/// Desktop entries of schemes
+const DESKTOP_ENTRIES: [(Schemes, &str); 2] = [
+    (
+        Schemes::MpvHandler,
+        include_str!("../../share/linux/mpv-handler.desktop"),
+    ),
+    (
+        Schemes::MpvHandlerDebug,
+        include_str!("../../share/linux/mpv-handler-debug.desktop"),
+    ),
+];
+
+/// Section of default applications in `mimeapps.list`
+const DEFAULT_APPLICATIONS: &str = "[Default Applications]";
+
+/// Register `mpv-handler://` and `mpv-handler-debug://` for current user
+///
+/// ```
+/// mpv-handler install
+/// ```
+///
+/// Write desktop entries to `$XDG_DATA_HOME/applications` and set them as
+/// default handlers in `$XDG_CONFIG_HOME/mimeapps.list`. If `Config.legacy_schemes`
+/// is enabled, the deprecated `mpv://` and `mpv-debug://` are registered too.
+pub fn exec(args: &[String]) -> Result<(), Error> {
+    if let Some(v) = args.first() {
+        return Err(Error::IncorrectArgument(v.to_string()));
+    }
+
+    let legacy = Config::load()?.legacy_schemes;
+    let (data, config) = dirs()?;
+    let exe = std::env::current_exe()?;
+
+    for path in install(&data, &config, &exe.to_string_lossy(), legacy)? {
+        println!("Installed: {}", path.display());
+    }
+
+    Ok(())
+}
+
+/// Unregister `mpv-handler://` and `mpv-handler-debug://` for current user
+///
+/// ```
+/// mpv-handler uninstall
+/// ```
+///
+/// The deprecated `mpv://` and `mpv-debug://` are always unregistered.
+pub fn uninstall_exec(args: &[String]) -> Result<(), Error> {
+    if let Some(v) = args.first() {
+        return Err(Error::IncorrectArgument(v.to_string()));
+    }
+
+    let (data, config) = dirs()?;
+
+    for path in uninstall(&data, &config)? {
+        println!("Removed: {}", path.display());
+    }
+
+    Ok(())
+}
+
+/// Returns user data and config directories (`$XDG_DATA_HOME`, `$XDG_CONFIG_HOME`)
+fn dirs() -> Result<(PathBuf, PathBuf), Error> {
+    match (dirs::data_dir(), dirs::config_dir()) {
+        (Some(data), Some(config)) => Ok((data, config)),
+        _ => Err(Error::MissingArgument("$HOME".to_string())),
+    }
+}
+
+/// Returns the file name of desktop entry
+pub fn desktop_name(scheme: &Schemes) -> String {
+    format!("{}.desktop", scheme.name())
+}
+
+/// Returns the MIME type of scheme
+pub fn mime_type(scheme: &Schemes) -> String {
+    format!("x-scheme-handler/{}", scheme.name())
+}
+
+/// Returns the MIME type of deprecated scheme
+fn legacy_mime_type(scheme: &Schemes) -> String {
+    format!("x-scheme-handler/{}", scheme.legacy_name())
+}
+
+/// Write desktop entries and update `mimeapps.list`, returns the written files
+///
+/// If `legacy`, desktop entries handle the deprecated schemes too.
+fn install(data: &Path, config: &Path, exe: &str, legacy: bool) -> Result<Vec<PathBuf>, Error> {
+    let applications = data.join("applications");
+    let mimeapps = config.join("mimeapps.list");
+    let mut paths: Vec<PathBuf> = Vec::new();
+
+    std::fs::create_dir_all(&applications)?;
+
+    for (scheme, entry) in DESKTOP_ENTRIES {
+        let path = applications.join(desktop_name(&scheme));
+        let legacy = legacy.then(|| scheme.legacy_name());
+        std::fs::write(&path, desktop_entry(entry, exe, legacy))?;
+        paths.push(path);
+    }
+
+    let mut list = read_optional(&mimeapps)?;
+
+    for (scheme, _) in DESKTOP_ENTRIES {
+        list = set_default(&list, &mime_type(&scheme), &desktop_name(&scheme));
+        list = match legacy {
+            true => set_default(&list, &legacy_mime_type(&scheme), &desktop_name(&scheme)),
+            false => unset_default(&list, &legacy_mime_type(&scheme), &desktop_name(&scheme)),
+        };
+    }
+
+    std::fs::create_dir_all(config)?;
+    std::fs::write(&mimeapps, list)?;
+    paths.push(mimeapps);
+
+    Ok(paths)
+}
+
+/// Remove desktop entries and the defaults in `mimeapps.list`, returns the removed files
+fn uninstall(data: &Path, config: &Path) -> Result<Vec<PathBuf>, Error> {
+    let applications = data.join("applications");
+    let mimeapps = config.join("mimeapps.list");
+    let mut paths: Vec<PathBuf> = Vec::new();
+
+    for (scheme, _) in DESKTOP_ENTRIES {
+        let path = applications.join(desktop_name(&scheme));
+        if path.exists() {
+            std::fs::remove_file(&path)?;
+            paths.push(path);
+        }
+    }
+
+    if mimeapps.exists() {
+        let mut list = read_optional(&mimeapps)?;
+
+        for (scheme, _) in DESKTOP_ENTRIES {
+            list = unset_default(&list, &mime_type(&scheme), &desktop_name(&scheme));
+            list = unset_default(&list, &legacy_mime_type(&scheme), &desktop_name(&scheme));
+        }
+
+        std::fs::write(&mimeapps, list)?;
+    }
+
+    Ok(paths)
+}
+
+/// Read file to string, returns empty string if it doesn't exist
+fn read_optional(path: &Path) -> Result<String, Error> {
+    match std::fs::read_to_string(path) {
+        Ok(v) => Ok(v),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(String::new()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Returns desktop entry with absolute `Exec=` path
+///
+/// The `legacy` scheme is added to `MimeType=` and `X-KDE-Protocols=`.
+fn desktop_entry(entry: &str, exe: &str, legacy: Option<&str>) -> String {
+    let mut data = String::new();
+
+    for line in entry.lines() {
+        match (line.split_once('='), legacy) {
+            (Some(("Exec", _)), _) => data.push_str(&format!("Exec={} %u", exec_arg(exe))),
+            (Some(("MimeType", _)), Some(v)) => {
+                data.push_str(&format!("{line}x-scheme-handler/{v};"))
+            }
+            (Some(("X-KDE-Protocols", _)), Some(v)) => data.push_str(&format!("{line},{v}")),
+            _ => data.push_str(line),
+        }
+        data.push('\n');
+    }
+
+    data
+}
+
+/// Quote the path as argument of `Exec=` key
+///
+/// In quoted argument, `"`, `` ` ``, `$` and `\` are escaped by backslash,
+/// then backslash is escaped again as string value. `%` is escaped as `%%`.
+fn exec_arg(path: &str) -> String {
+    let mut arg = String::from("\"");
+
+    for c in path.chars() {
+        match c {
+            '"' | '`' | '$' => arg.push_str(&format!("\\\\{c}")),
+            '\\' => arg.push_str("\\\\\\\\"),
+            '%' => arg.push_str("%%"),
+            _ => arg.push(c),
+        }
+    }
+
+    arg.push('"');
+    arg
+}
+
+/// Set the default application of MIME type in `mimeapps.list`
+fn set_default(list: &str, mime: &str, desktop: &str) -> String {
+    let mut lines: Vec<String> = Vec::new();
+    let mut section = false;
+    let mut found = false;
+
+    for line in list.lines() {
+        if line.starts_with('[') {
+            // Append to the end of default applications section
+            if section && !found {
+                insert_last(&mut lines, format!("{mime}={desktop};"));
+                found = true;
+            }
+            section = line.trim() == DEFAULT_APPLICATIONS;
+        } else if section && key(line) == Some(mime) {
+            if !found {
+                lines.push(format!("{mime}={desktop};"));
+                found = true;
+            }
+            continue;
+        }
+
+        lines.push(line.to_string());
+    }
+
+    if !found {
+        if !section {
+            if lines.last().is_some_and(|v| !v.is_empty()) {
+                lines.push(String::new());
+            }
+            lines.push(DEFAULT_APPLICATIONS.to_string());
+        }
+        lines.push(format!("{mime}={desktop};"));
+    }
+
+    lines.join("\n") + "\n"
+}
+
+/// Remove the desktop entry from the default applications of MIME type in `mimeapps.list`
+fn unset_default(list: &str, mime: &str, desktop: &str) -> String {
+    let mut lines: Vec<String> = Vec::new();
+    let mut section = false;
+
+    for line in list.lines() {
+        if line.starts_with('[') {
+            section = line.trim() == DEFAULT_APPLICATIONS;
+        } else if section
+            && key(line) == Some(mime)
+            && let Some((_, v)) = line.split_once('=')
+        {
+            let others: Vec<&str> = v
+                .split(';')
+                .map(|v| v.trim())
+                .filter(|v| !v.is_empty() && *v != desktop)
+                .collect();
+
+            if !others.is_empty() {
+                lines.push(format!("{mime}={};", others.join(";")));
+            }
+            continue;
+        }
+
+        lines.push(line.to_string());
+    }
+
+    match lines.is_empty() {
+        true => String::new(),
+        false => lines.join("\n") + "\n",
+    }
+}
+
+/// Returns the first default application of MIME type in `mimeapps.list`
+pub fn default_application<'a>(list: &'a str, mime: &str) -> Option<&'a str> {
+    let mut section = false;
+
+    for line in list.lines() {
+        if line.starts_with('[') {
+            section = line.trim() == DEFAULT_APPLICATIONS;
+        } else if section
+            && key(line) == Some(mime)
+            && let Some((_, v)) = line.split_once('=')
+        {
+            return v.split(';').map(|v| v.trim()).find(|v| !v.is_empty());
+        }
+    }
+
+    None
+}
+
+/// Returns the key of `key=value` line
+fn key(line: &str) -> Option<&str> {
+    line.split_once('=').map(|(k, _)| k.trim())
+}
+
+/// Insert the line after the last non-empty line
+fn insert_last(lines: &mut Vec<String>, line: String) {
+    let i = lines
+        .iter()
+        .rposition(|v| !v.trim().is_empty())
+        .map_or(0, |i| i + 1);
+    lines.insert(i, line);
+}
+
+#[test]
+fn test_desktop_entry() {
+    let entry = desktop_entry(
+        DESKTOP_ENTRIES[0].1,
+        "/home/user/.local/bin/mpv-handler",
+        None,
+    );
+    assert!(entry.contains("\nExec=\"/home/user/.local/bin/mpv-handler\" %u\n"));
+    assert!(entry.contains("\nMimeType=x-scheme-handler/mpv-handler;\n"));
+    assert!(entry.contains("\nX-KDE-Protocols=mpv-handler\n"));
+
+    let entry = desktop_entry(DESKTOP_ENTRIES[1].1, "mpv-handler", Some("mpv-debug"));
+    assert!(
+        entry.contains(
+            "\nMimeType=x-scheme-handler/mpv-handler-debug;x-scheme-handler/mpv-debug;\n"
+        )
+    );
+    assert!(entry.contains("\nX-KDE-Protocols=mpv-handler-debug,mpv-debug\n"));
+
+    assert_eq!(exec_arg("/opt/a b/mpv-handler"), "\"/opt/a b/mpv-handler\"");
+    assert_eq!(exec_arg("/opt/$x%/m\"h"), "\"/opt/\\\\$x%%/m\\\\\"h\"");
+}
+
+#[test]
+fn test_mimeapps_list() {
+    let mime = "x-scheme-handler/mpv-handler";
+    let desktop = "mpv-handler.desktop";
+
+    // Empty list
+    let list = set_default("", mime, desktop);
+    assert_eq!(
+        list,
+        "[Default Applications]\nx-scheme-handler/mpv-handler=mpv-handler.desktop;\n"
+    );
+    assert_eq!(default_application(&list, mime), Some(desktop));
+    assert_eq!(
+        unset_default(&list, mime, desktop),
+        "[Default Applications]\n"
+    );
+
+    // Replace old handler and keep others
+    let old = "[Added Associations]\nx-scheme-handler/mpv-handler=old.desktop;\n\n[Default Applications]\ntext/html=firefox.desktop\nx-scheme-handler/mpv-handler=old.desktop\n\n[Removed Associations]\n";
+    let list = set_default(old, mime, desktop);
+    assert_eq!(
+        list,
+        "[Added Associations]\nx-scheme-handler/mpv-handler=old.desktop;\n\n[Default Applications]\ntext/html=firefox.desktop\nx-scheme-handler/mpv-handler=mpv-handler.desktop;\n\n[Removed Associations]\n"
+    );
+
+    assert_eq!(default_application(old, mime), Some("old.desktop"));
+    assert_eq!(default_application(old, "text/plain"), None);
+
+    // Append to section
+    let list = set_default(
+        "[Default Applications]\ntext/html=firefox.desktop\n\n[Added Associations]\n",
+        mime,
+        desktop,
+    );
+    assert_eq!(
+        list,
+        "[Default Applications]\ntext/html=firefox.desktop\nx-scheme-handler/mpv-handler=mpv-handler.desktop;\n\n[Added Associations]\n"
+    );
+    assert_eq!(
+        unset_default(&list, mime, desktop),
+        "[Default Applications]\ntext/html=firefox.desktop\n\n[Added Associations]\n"
+    );
+
+    // Keep other handlers of the same MIME type
+    let list =
+        "[Default Applications]\nx-scheme-handler/mpv-handler=mpv-handler.desktop;other.desktop;\n";
+    assert_eq!(
+        unset_default(list, mime, desktop),
+        "[Default Applications]\nx-scheme-handler/mpv-handler=other.desktop;\n"
+    );
+}
+
+#[test]
+fn test_install_uninstall() {
+    let root = crate::testing::temp_path("install");
+    let data = root.join("data");
+    let config = root.join("config");
+
+    std::fs::create_dir_all(&config).unwrap();
+    std::fs::write(
+        config.join("mimeapps.list"),
+        "[Default Applications]\ntext/html=firefox.desktop\n",
+    )
+    .unwrap();
+
+    let paths = install(&data, &config, "/usr/local/bin/mpv-handler", false).unwrap();
+    assert_eq!(
+        paths,
+        [
+            data.join("applications/mpv-handler.desktop"),
+            data.join("applications/mpv-handler-debug.desktop"),
+            config.join("mimeapps.list"),
+        ]
+    );
+
+    let entry = std::fs::read_to_string(&paths[1]).unwrap();
+    assert!(entry.contains("\nExec=\"/usr/local/bin/mpv-handler\" %u\n"));
+    assert!(entry.contains("\nTerminal=true\n"));
+
+    let list = std::fs::read_to_string(&paths[2]).unwrap();
+    assert_eq!(
+        list,
+        "[Default Applications]\ntext/html=firefox.desktop\nx-scheme-handler/mpv-handler=mpv-handler.desktop;\nx-scheme-handler/mpv-handler-debug=mpv-handler-debug.desktop;\n"
+    );
+
+    // Install again doesn't duplicate entries
+    install(&data, &config, "/usr/local/bin/mpv-handler", false).unwrap();
+    assert_eq!(std::fs::read_to_string(&paths[2]).unwrap(), list);
+
+    let removed = uninstall(&data, &config).unwrap();
+    assert_eq!(removed, paths[..2]);
+    assert!(!paths[0].exists() && !paths[1].exists());
+    assert_eq!(
+        std::fs::read_to_string(&paths[2]).unwrap(),
+        "[Default Applications]\ntext/html=firefox.desktop\n"
+    );
+
+    // Deprecated schemes
+    install(&data, &config, "/usr/local/bin/mpv-handler", true).unwrap();
+    let list = std::fs::read_to_string(&paths[2]).unwrap();
+    assert_eq!(
+        default_application(&list, "x-scheme-handler/mpv"),
+        Some("mpv-handler.desktop")
+    );
+    assert_eq!(
+        default_application(&list, "x-scheme-handler/mpv-debug"),
+        Some("mpv-handler-debug.desktop")
+    );
+
+    uninstall(&data, &config).unwrap();
+    assert_eq!(
+        std::fs::read_to_string(&paths[2]).unwrap(),
+        "[Default Applications]\ntext/html=firefox.desktop\n"
+    );
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
@@ -0,0 +1,324 @@
+use crate::error::Error;
+use crate::native::HOST_NAME;
+use serde_json::{Value, json};
+use std::path::{Path, PathBuf};
+
+/// Browser family of native messaging host manifest
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Browser {
+    Firefox,
+    Chromium,
+}
+
+/// Firefox manifest directory, relative to home directory
+#[cfg(all(unix, not(target_os = "macos")))]
+const FIREFOX_DIR: &str = ".mozilla/native-messaging-hosts";
+#[cfg(target_os = "macos")]
+const FIREFOX_DIR: &str = "Library/Application Support/Mozilla/NativeMessagingHosts";
+
+/// Chromium based browsers, the directories are relative to user config directory
+#[cfg(all(unix, not(target_os = "macos")))]
+const CHROMIUM_DIRS: [&str; 5] = [
+    "chromium",
+    "google-chrome",
+    "BraveSoftware/Brave-Browser",
+    "microsoft-edge",
+    "vivaldi",
+];
+#[cfg(target_os = "macos")]
+const CHROMIUM_DIRS: [&str; 5] = [
+    "Chromium",
+    "Google/Chrome",
+    "BraveSoftware/Brave-Browser",
+    "Microsoft Edge",
+    "Vivaldi",
+];
+
+/// Registry keys of native messaging hosts (under `HKEY_CURRENT_USER`)
+#[cfg(windows)]
+const REGISTRY_KEYS: [(Browser, &str); 4] = [
+    (Browser::Firefox, r"Software\Mozilla\NativeMessagingHosts"),
+    (Browser::Chromium, r"Software\Chromium\NativeMessagingHosts"),
+    (
+        Browser::Chromium,
+        r"Software\Google\Chrome\NativeMessagingHosts",
+    ),
+    (
+        Browser::Chromium,
+        r"Software\Microsoft\Edge\NativeMessagingHosts",
+    ),
+];
+
+/// Install native messaging host manifests for Firefox and Chromium
+///
+/// ```
+/// mpv-handler native-manifest [--firefox <EXTENSION_ID>]... [--chromium <EXTENSION_ID>]...
+/// ```
+pub fn exec(args: &[String]) -> Result<(), Error> {
+    let mut firefox: Vec<String> = Vec::new();
+    let mut chromium: Vec<String> = Vec::new();
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--firefox" => firefox.push(firefox_id(crate::commands::value(&mut args, arg)?)?),
+            "--chromium" => chromium.push(chromium_id(crate::commands::value(&mut args, arg)?)?),
+            v => return Err(Error::IncorrectArgument(v.to_string())),
+        }
+    }
+
+    if firefox.is_empty() && chromium.is_empty() {
+        return Err(Error::MissingArgument("--firefox/--chromium".to_string()));
+    }
+
+    let exe = std::env::current_exe()?.to_string_lossy().to_string();
+    let targets = targets()?;
+    let paths = install(&targets, &exe, &firefox, &chromium)?;
+
+    for (browser, path) in &paths {
+        println!("Installed: {}", path.display());
+
+        #[cfg(windows)]
+        register(*browser, path)?;
+        #[cfg(not(windows))]
+        let _ = browser;
+    }
+
+    Ok(())
+}
+
+/// Returns the manifest directories of each browser
+#[cfg(unix)]
+fn targets() -> Result<Vec<(Browser, PathBuf)>, Error> {
+    match (dirs::home_dir(), dirs::config_dir()) {
+        (Some(home), Some(config)) => Ok(targets_in(&home, &config)),
+        _ => Err(Error::MissingArgument("$HOME".to_string())),
+    }
+}
+
+/// Returns the manifest directories of each browser, in given home and config directory
+///
+/// Chromium based browsers are only included if their config directory exists,
+/// except Chromium itself.
+#[cfg(unix)]
+fn targets_in(home: &Path, config: &Path) -> Vec<(Browser, PathBuf)> {
+    let mut targets = vec![(Browser::Firefox, home.join(FIREFOX_DIR))];
+
+    for (i, dir) in CHROMIUM_DIRS.iter().enumerate() {
+        let dir = config.join(dir);
+        if i == 0 || dir.is_dir() {
+            targets.push((Browser::Chromium, dir.join("NativeMessagingHosts")));
+        }
+    }
+
+    targets
+}
+
+/// Returns the manifest directories of each browser
+///
+/// The manifests are saved in config directory, and registered to registry.
+#[cfg(windows)]
+fn targets() -> Result<Vec<(Browser, PathBuf)>, Error> {
+    let dir = match crate::config::get_config_dir() {
+        Some(v) => v.join("native-messaging"),
+        None => return Err(Error::MissingConfig("config_dir".to_string())),
+    };
+
+    Ok(vec![
+        (Browser::Firefox, dir.join("firefox")),
+        (Browser::Chromium, dir.join("chromium")),
+    ])
+}
+
+/// Register the manifest path to registry of the browser family
+#[cfg(windows)]
+fn register(browser: Browser, path: &Path) -> Result<(), Error> {
+    for (_, key) in REGISTRY_KEYS.iter().filter(|(b, _)| *b == browser) {
+        let key = format!(r"HKCU\{key}\{HOST_NAME}");
+        let status = std::process::Command::new("reg")
+            .args(["add", &key, "/ve", "/t", "REG_SZ", "/d"])
+            .arg(path)
+            .arg("/f")
+            .status()?;
+
+        if !status.success() {
+            return Err(Error::NativeRunFailed(format!("reg add {key}")));
+        }
+        println!("Registered: {key}");
+    }
+
+    Ok(())
+}
+
+/// Write manifests to target directories, returns the written files
+///
+/// The browsers without any allowed extension are skipped.
+fn install(
+    targets: &[(Browser, PathBuf)],
+    exe: &str,
+    firefox: &[String],
+    chromium: &[String],
+) -> Result<Vec<(Browser, PathBuf)>, Error> {
+    let mut paths: Vec<(Browser, PathBuf)> = Vec::new();
+
+    for (browser, dir) in targets {
+        let ids = match browser {
+            Browser::Firefox => firefox,
+            Browser::Chromium => chromium,
+        };
+
+        if ids.is_empty() {
+            continue;
+        }
+
+        let path = dir.join(format!("{HOST_NAME}.json"));
+        let data = serde_json::to_string_pretty(&manifest(*browser, exe, ids))?;
+
+        std::fs::create_dir_all(dir)?;
+        std::fs::write(&path, data + "\n")?;
+        paths.push((*browser, path));
+    }
+
+    Ok(paths)
+}
+
+/// Returns native messaging host manifest
+fn manifest(browser: Browser, exe: &str, ids: &[String]) -> Value {
+    let mut manifest = json!({
+        "name": HOST_NAME,
+        "description": "mpv-handler native messaging host",
+        "path": exe,
+        "type": "stdio",
+    });
+
+    match browser {
+        Browser::Firefox => manifest["allowed_extensions"] = json!(ids),
+        Browser::Chromium => {
+            let origins: Vec<String> = ids
+                .iter()
+                .map(|v| format!("chrome-extension://{v}/"))
+                .collect();
+            manifest["allowed_origins"] = json!(origins);
+        }
+    }
+
+    manifest
+}
+
+/// Check Firefox extension ID, e.g. `name@example.com` or `{UUID}`
+fn firefox_id(id: &str) -> Result<String, Error> {
+    let allowed = |c: char| c.is_ascii_alphanumeric() || "@.-_{}".contains(c);
+
+    match !id.is_empty() && id.chars().all(allowed) {
+        true => Ok(id.to_string()),
+        false => Err(Error::IncorrectArgument(id.to_string())),
+    }
+}
+
+/// Check Chromium extension ID, the origin `chrome-extension://ID/` is also accepted
+fn chromium_id(id: &str) -> Result<String, Error> {
+    let v = id.strip_prefix("chrome-extension://").unwrap_or(id);
+    let v = v.strip_suffix('/').unwrap_or(v);
+
+    match !v.is_empty() && v.chars().all(|c| c.is_ascii_alphanumeric()) {
+        true => Ok(v.to_string()),
+        false => Err(Error::IncorrectArgument(id.to_string())),
+    }
+}
+
+#[test]
+fn test_manifest() {
+    let ids = vec!["mpv-handler@example.com".to_string()];
+    let m = manifest(Browser::Firefox, "/usr/bin/mpv-handler", &ids);
+    assert_eq!(m["name"], HOST_NAME);
+    assert_eq!(m["path"], "/usr/bin/mpv-handler");
+    assert_eq!(m["type"], "stdio");
+    assert_eq!(m["allowed_extensions"][0], "mpv-handler@example.com");
+    assert!(m.get("allowed_origins").is_none());
+
+    let ids = vec!["abcdefghijklmnop".to_string()];
+    let m = manifest(Browser::Chromium, "/usr/bin/mpv-handler", &ids);
+    assert_eq!(
+        m["allowed_origins"][0],
+        "chrome-extension://abcdefghijklmnop/"
+    );
+    assert!(m.get("allowed_extensions").is_none());
+}
+
+#[test]
+fn test_extension_id() {
+    assert_eq!(
+        firefox_id("{b9db16a4-6edc-47ec-a1f4-b86292ed211d}").unwrap(),
+        "{b9db16a4-6edc-47ec-a1f4-b86292ed211d}"
+    );
+    assert!(matches!(firefox_id(""), Err(Error::IncorrectArgument(_))));
+    assert!(matches!(
+        firefox_id("a\"b"),
+        Err(Error::IncorrectArgument(_))
+    ));
+
+    assert_eq!(chromium_id("abcdefghijklmnop").unwrap(), "abcdefghijklmnop");
+    assert_eq!(
+        chromium_id("chrome-extension://abcdefghijklmnop/").unwrap(),
+        "abcdefghijklmnop"
+    );
+    assert!(matches!(
+        chromium_id("abc/def"),
+        Err(Error::IncorrectArgument(_))
+    ));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_manifest_install() {
+    let root = crate::testing::temp_path("manifest");
+    let home = root.join("home");
+    let config = home.join(".config");
+
+    std::fs::create_dir_all(config.join(CHROMIUM_DIRS[1])).unwrap();
+
+    let targets = targets_in(&home, &config);
+    assert_eq!(targets.len(), 3);
+
+    // Only Chromium manifests
+    let paths = install(
+        &targets,
+        "/usr/bin/mpv-handler",
+        &[],
+        &["abcdefghijklmnop".to_string()],
+    )
+    .unwrap();
+    assert_eq!(
+        paths,
+        [
+            (
+                Browser::Chromium,
+                config
+                    .join(CHROMIUM_DIRS[0])
+                    .join("NativeMessagingHosts/mpv_handler.json")
+            ),
+            (
+                Browser::Chromium,
+                config
+                    .join(CHROMIUM_DIRS[1])
+                    .join("NativeMessagingHosts/mpv_handler.json")
+            ),
+        ]
+    );
+    assert!(!home.join(FIREFOX_DIR).exists());
+
+    let paths = install(
+        &targets,
+        "/usr/bin/mpv-handler",
+        &["mpv-handler@example.com".to_string()],
+        &[],
+    )
+    .unwrap();
+    assert_eq!(paths[0].1, home.join(FIREFOX_DIR).join("mpv_handler.json"));
+
+    let data = std::fs::read_to_string(&paths[0].1).unwrap();
+    let m: Value = serde_json::from_str(&data).unwrap();
+    assert_eq!(m["allowed_extensions"][0], "mpv-handler@example.com");
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
@@ -0,0 +1,19 @@
+use crate::error::Error;
+
+pub mod decode;
+pub mod doctor;
+pub mod encode;
+pub mod history;
+#[cfg(unix)]
+pub mod install;
+pub mod manifest;
+pub mod regfile;
+pub mod serve;
+
+/// Returns the value of option argument, e.g. `--quality 1080p`
+pub fn value<'a>(args: &mut std::slice::Iter<'a, String>, name: &str) -> Result<&'a str, Error> {
+    match args.next() {
+        Some(v) => Ok(v),
+        None => Err(Error::MissingArgument(name.to_string())),
+    }
+}
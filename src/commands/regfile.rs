@@ -0,0 +1,243 @@
+use crate::config::Config;
+use crate::error::Error;
+use crate::protocol::Schemes;
+use std::path::{Path, PathBuf};
+
+/// Registry key of per-user classes
+const CLASSES_KEY: &str = r"HKEY_CURRENT_USER\Software\Classes";
+
+/// Schemes with their descriptions and binary names
+const SCHEMES: [(Schemes, &str, &str); 2] = [
+    (Schemes::MpvHandler, "URL:MPV Handler", "mpv-handler.exe"),
+    (
+        Schemes::MpvHandlerDebug,
+        "URL:MPV Handler Debug",
+        "mpv-handler-debug.exe",
+    ),
+];
+
+/// Write Windows registry file to register (or unregister) protocol handlers for current user
+///
+/// ```
+/// mpv-handler reg-file [--uninstall] [--output <FILE>]
+/// ```
+///
+/// The default output is `mpv-handler-install.reg` (or `mpv-handler-uninstall.reg`)
+/// next to the binary, `--output -` prints to stdout. If `Config.legacy_schemes` is enabled,
+/// the deprecated `mpv://` and `mpv-debug://` are registered too.
+pub fn exec(args: &[String]) -> Result<(), Error> {
+    let mut uninstall = false;
+    let mut output: Option<&str> = None;
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--uninstall" => uninstall = true,
+            "--output" => output = Some(crate::commands::value(&mut args, arg)?),
+            v => return Err(Error::IncorrectArgument(v.to_string())),
+        }
+    }
+
+    let exe = realpath(&std::env::current_exe()?);
+    let dir = exe.parent().map(Path::to_path_buf).unwrap_or_default();
+
+    let data = match uninstall {
+        true => uninstall_file(),
+        false => {
+            let config = Config::load()?;
+            let binaries = binaries(&dir, &exe);
+            let icon = icon(&config, &exe)?;
+            install_file(&binaries, &icon, config.legacy_schemes)
+        }
+    };
+
+    match output {
+        Some("-") => print!("{data}"),
+        _ => {
+            let path = match output {
+                Some(v) => PathBuf::from(v),
+                None => dir.join(match uninstall {
+                    true => "mpv-handler-uninstall.reg",
+                    false => "mpv-handler-install.reg",
+                }),
+            };
+            std::fs::write(&path, encode(&data))?;
+            println!("Written: {}", path.display());
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns canonical path without verbatim prefix `\\?\`
+fn realpath(path: &Path) -> PathBuf {
+    let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+
+    match path.to_str().and_then(|v| v.strip_prefix(r"\\?\")) {
+        Some(v) => PathBuf::from(v),
+        None => path,
+    }
+}
+
+/// Returns binary paths of each scheme
+///
+/// `mpv-handler-debug.exe` is the console build, if it's not next to current binary,
+/// current binary is used for both schemes.
+fn binaries(dir: &Path, exe: &Path) -> [String; 2] {
+    SCHEMES.map(|(_, _, name)| {
+        let path = dir.join(name);
+        match path.exists() {
+            true => path.display().to_string(),
+            false => exe.display().to_string(),
+        }
+    })
+}
+
+/// Returns the icon of protocol handlers
+///
+/// Use the icon of `mpv.exe` (`mpv.com` has no icon), or current binary if mpv isn't found.
+fn icon(config: &Config, exe: &Path) -> Result<String, Error> {
+    let mpv = match &config.mpv {
+        Some(v) => v.clone(),
+        None => crate::config::default_mpv()?,
+    };
+    let mpv = match mpv.strip_suffix(".com") {
+        Some(v) => PathBuf::from(format!("{v}.exe")),
+        None => PathBuf::from(mpv),
+    };
+
+    match mpv.is_absolute() && mpv.exists() {
+        true => Ok(format!("\"{}\",0", realpath(&mpv).display())),
+        false => Ok(format!("\"{}\",0", exe.display())),
+    }
+}
+
+/// Returns registry file to register protocol handlers, and the deprecated schemes if `legacy`
+fn install_file(binaries: &[String; 2], icon: &str, legacy: bool) -> String {
+    let mut data = String::from("Windows Registry Editor Version 5.00\r\n");
+    let mut schemes: Vec<(&str, &str, &String)> = SCHEMES
+        .iter()
+        .zip(binaries)
+        .map(|((scheme, description, _), binary)| (scheme.name(), *description, binary))
+        .collect();
+
+    if legacy {
+        for ((scheme, description, _), binary) in SCHEMES.iter().zip(binaries) {
+            schemes.push((scheme.legacy_name(), description, binary));
+        }
+    }
+
+    for (name, description, binary) in schemes {
+        let key = format!(r"{CLASSES_KEY}\{name}");
+        let command = format!("\"{binary}\" \"%1\"");
+
+        data.push_str(&format!("\r\n[{key}]\r\n"));
+        data.push_str(&format!("@={}\r\n", reg_string(description)));
+        data.push_str(&format!(
+            "\"Content Type\"={}\r\n",
+            reg_string(&format!("application/x-{name}"))
+        ));
+        data.push_str("\"URL Protocol\"=\"\"\r\n");
+        data.push_str(&format!("\r\n[{key}\\DefaultIcon]\r\n"));
+        data.push_str(&format!("@={}\r\n", reg_string(icon)));
+        data.push_str(&format!("\r\n[{key}\\shell\\open\\command]\r\n"));
+        data.push_str(&format!("@={}\r\n", reg_string(&command)));
+    }
+
+    data
+}
+
+/// Returns registry file to unregister protocol handlers, including the deprecated schemes
+///
+/// Only the per-user keys are removed, the system-wide keys written by `handler-install.bat`
+/// of previous versions require administrator privileges (see README).
+fn uninstall_file() -> String {
+    let mut data = String::from("Windows Registry Editor Version 5.00\r\n\r\n");
+    let names = SCHEMES
+        .iter()
+        .map(|(scheme, _, _)| scheme.name())
+        .chain(SCHEMES.iter().map(|(scheme, _, _)| scheme.legacy_name()));
+
+    for name in names {
+        data.push_str(&format!(r"[-{CLASSES_KEY}\{name}]"));
+        data.push_str("\r\n");
+    }
+
+    data
+}
+
+/// Returns quoted string value of registry file
+fn reg_string(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', r"\\").replace('"', "\\\""))
+}
+
+/// Encode registry file as UTF-16LE with BOM, which is expected by `regedit`
+fn encode(data: &str) -> Vec<u8> {
+    let mut bytes: Vec<u8> = vec![0xFF, 0xFE];
+
+    for v in data.encode_utf16() {
+        bytes.extend_from_slice(&v.to_le_bytes());
+    }
+
+    bytes
+}
+
+#[test]
+fn test_install_file() {
+    let binaries = [
+        r"C:\Program Files\mpv-handler\mpv-handler.exe".to_string(),
+        r"C:\Program Files\mpv-handler\mpv-handler-debug.exe".to_string(),
+    ];
+    let icon = r#""C:\Program Files\mpv\mpv.exe",0"#;
+
+    let data = install_file(&binaries, icon, false);
+    let golden = include_str!("../../tests/data/mpv-handler-install.reg");
+
+    // Lines end with CRLF
+    assert!(data.split("\r\n").all(|v| !v.contains('\n')));
+    assert_eq!(data.replace("\r\n", "\n"), golden);
+
+    let data = install_file(&binaries, icon, true);
+    let golden = include_str!("../../tests/data/mpv-handler-install-legacy.reg");
+    assert_eq!(data.replace("\r\n", "\n"), golden);
+}
+
+#[test]
+fn test_uninstall_file() {
+    let golden = include_str!("../../tests/data/mpv-handler-uninstall.reg");
+    assert_eq!(uninstall_file().replace("\r\n", "\n"), golden);
+}
+
+#[test]
+fn test_reg_string() {
+    assert_eq!(
+        reg_string(r#""C:\a b\c.exe" "%1""#),
+        r#""\"C:\\a b\\c.exe\" \"%1\"""#
+    );
+}
+
+#[test]
+fn test_encode() {
+    assert_eq!(encode("W\r\n"), [0xFF, 0xFE, b'W', 0, b'\r', 0, b'\n', 0]);
+}
+
+#[test]
+fn test_binaries() {
+    let dir = crate::testing::temp_path("regfile");
+    let exe = dir.join("mpv-handler.exe");
+
+    std::fs::create_dir_all(&dir).unwrap();
+
+    // Without debug binary
+    let b = binaries(&dir, &exe);
+    assert_eq!(b, [exe.display().to_string(), exe.display().to_string()]);
+
+    std::fs::write(dir.join("mpv-handler-debug.exe"), "").unwrap();
+    let b = binaries(&dir, &exe);
+    assert_eq!(
+        b[1],
+        dir.join("mpv-handler-debug.exe").display().to_string()
+    );
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
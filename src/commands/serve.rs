@@ -0,0 +1,436 @@
+use crate::config::Config;
+use crate::error::Error;
+use serde_json::json;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::sync::Arc;
+
+/// Default listening port
+const DEFAULT_PORT: u16 = 3210;
+
+/// Max length of request line and headers
+const MAX_LENGTH: u64 = 8 * 1024;
+
+/// Header of shared token
+const TOKEN_HEADER: &str = "X-Mpv-Handler-Token";
+
+/// Function to run mpv-handler link, returns the video URL
+type Runner = fn(&str) -> Result<String, Error>;
+
+/// HTTP request (only request line and headers)
+struct Request {
+    method: String,
+    path: String,
+    headers: Vec<(String, String)>,
+}
+
+/// HTTP response with JSON body
+struct Response {
+    status: u16,
+    headers: Vec<(&'static str, String)>,
+    body: String,
+}
+
+/// Run HTTP bridge on loopback address
+///
+/// ```
+/// mpv-handler serve [--port <PORT>]
+/// ```
+///
+/// The request `GET /PLUGIN/ENCODED_URL/?PARAMETERS` runs the link
+/// `mpv-handler://PLUGIN/ENCODED_URL/?PARAMETERS`, if the `Origin` is allowed by
+/// `Config.serve_origins` and the header `X-Mpv-Handler-Token` is `Config.serve_token`.
+pub fn exec(args: &[String]) -> Result<(), Error> {
+    let mut port: Option<u16> = None;
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--port" => {
+                let v = crate::commands::value(&mut args, arg)?;
+                port = Some(
+                    v.parse()
+                        .map_err(|_| Error::IncorrectArgument(v.to_string()))?,
+                );
+            }
+            v => return Err(Error::IncorrectArgument(v.to_string())),
+        }
+    }
+
+    let config = Config::load()?;
+
+    // Refuse to serve without token, any local process could run links
+    if config.serve_token.as_deref().unwrap_or_default().is_empty() {
+        return Err(Error::MissingConfig("serve_token".to_string()));
+    }
+
+    let port = port.or(config.serve_port).unwrap_or(DEFAULT_PORT);
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))?;
+
+    println!("Listening: http://{}/", listener.local_addr()?);
+
+    serve(listener, Arc::new(config), crate::native::run)
+}
+
+/// Accept connections and handle each one in a new thread
+fn serve(listener: TcpListener, config: Arc<Config>, run: Runner) -> Result<(), Error> {
+    let port = listener.local_addr()?.port();
+
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(v) => v,
+            Err(e) => {
+                eprintln!("{}", Error::FromIoError(e));
+                continue;
+            }
+        };
+        let config = config.clone();
+
+        std::thread::spawn(move || {
+            if let Err(e) = handle(stream, &config, port, run) {
+                eprintln!("{e}");
+            }
+        });
+    }
+
+    Ok(())
+}
+
+/// Read request and write response
+fn handle(stream: TcpStream, config: &Config, port: u16, run: Runner) -> Result<(), Error> {
+    stream.set_read_timeout(Some(std::time::Duration::from_secs(10)))?;
+
+    let mut reader = BufReader::new((&stream).take(MAX_LENGTH));
+    let response = match read_request(&mut reader) {
+        Ok(request) => respond(&request, config, port, run),
+        Err(e) => Response::error(400, &e),
+    };
+
+    (&stream).write_all(&response.to_bytes())?;
+
+    Ok(())
+}
+
+/// Returns response of the request
+fn respond(request: &Request, config: &Config, port: u16, run: Runner) -> Response {
+    // Check `Host` to prevent DNS rebinding
+    let host = request.header("Host").unwrap_or_default();
+    if host != format!("127.0.0.1:{port}") && host != format!("localhost:{port}") {
+        return Response::message(403, "Forbidden host");
+    }
+
+    // Check `Origin` of browser requests, requests without `Origin` are not from web pages
+    let origin = request.header("Origin");
+    if let Some(v) = origin
+        && !config
+            .serve_origins
+            .iter()
+            .any(|p| crate::config::glob_match(p, v))
+    {
+        return Response::message(403, "Forbidden origin");
+    }
+
+    let response = match request.method.as_str() {
+        "OPTIONS" => Response::preflight(),
+        "GET" => match token_eq(
+            request.header(TOKEN_HEADER).unwrap_or_default(),
+            config.serve_token.as_deref().unwrap_or_default(),
+        ) {
+            true => play(&request.path, run),
+            false => Response::message(401, "Incorrect token"),
+        },
+        _ => Response::message(405, "Method not allowed"),
+    };
+
+    match origin {
+        Some(v) => response.allow_origin(v),
+        None => response,
+    }
+}
+
+/// Run the link of request path
+///
+/// The response is written once the player is started, see `crate::native::run`.
+fn play(path: &str, run: Runner) -> Response {
+    let link = match path.strip_prefix('/') {
+        Some(v) if !v.is_empty() => format!("mpv-handler://{v}"),
+        _ => return Response::message(404, "Not found"),
+    };
+
+    match run(&link) {
+        Ok(url) => Response::json(200, json!({ "ok": true, "url": url })),
+        Err(e) => Response::error(400, &e),
+    }
+}
+
+/// Compare tokens in constant time, empty token never matches
+fn token_eq(a: &str, b: &str) -> bool {
+    if a.is_empty() || a.len() != b.len() {
+        return false;
+    }
+
+    a.bytes()
+        .zip(b.bytes())
+        .fold(0, |acc, (x, y)| acc | (x ^ y))
+        == 0
+}
+
+/// Read request line and headers
+fn read_request<R: BufRead>(reader: &mut R) -> Result<Request, Error> {
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+
+    let mut parts = line.split_whitespace();
+    let (method, path) = match (parts.next(), parts.next(), parts.next()) {
+        (Some(m), Some(p), Some(v)) if v.starts_with("HTTP/1.") => (m.to_string(), p.to_string()),
+        _ => return Err(Error::IncorrectRequest(line.trim().to_string())),
+    };
+
+    let mut headers: Vec<(String, String)> = Vec::new();
+
+    loop {
+        line.clear();
+
+        if reader.read_line(&mut line)? == 0 {
+            return Err(Error::IncorrectRequest("incomplete headers".to_string()));
+        }
+
+        let header = line.trim_end_matches(['\r', '\n']);
+
+        if header.is_empty() {
+            break;
+        }
+
+        match header.split_once(':') {
+            Some((k, v)) => headers.push((k.trim().to_string(), v.trim().to_string())),
+            None => return Err(Error::IncorrectRequest(header.to_string())),
+        }
+    }
+
+    Ok(Request {
+        method,
+        path,
+        headers,
+    })
+}
+
+impl Request {
+    /// Returns the value of header (case-insensitive name)
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+impl Response {
+    /// Returns response with JSON body
+    fn json(status: u16, body: serde_json::Value) -> Response {
+        Response {
+            status,
+            headers: Vec::new(),
+            body: body.to_string(),
+        }
+    }
+
+    /// Returns failed response with message
+    fn message(status: u16, message: &str) -> Response {
+        Response::json(status, json!({ "ok": false, "error": message }))
+    }
+
+    /// Returns failed response of error
+    fn error(status: u16, e: &Error) -> Response {
+        Response::message(status, &e.to_string())
+    }
+
+    /// Returns response of CORS preflight request
+    fn preflight() -> Response {
+        let mut response = Response::json(204, serde_json::Value::Null);
+        response.body.clear();
+        response.headers = vec![
+            ("Access-Control-Allow-Methods", "GET".to_string()),
+            ("Access-Control-Allow-Headers", TOKEN_HEADER.to_string()),
+            ("Access-Control-Allow-Private-Network", "true".to_string()),
+            ("Access-Control-Max-Age", "600".to_string()),
+        ];
+        response
+    }
+
+    /// Allow the origin to read response
+    fn allow_origin(mut self, origin: &str) -> Response {
+        self.headers
+            .push(("Access-Control-Allow-Origin", origin.to_string()));
+        self.headers.push(("Vary", "Origin".to_string()));
+        self
+    }
+
+    /// Returns HTTP response data
+    fn to_bytes(&self) -> Vec<u8> {
+        let reason = match self.status {
+            200 => "OK",
+            204 => "No Content",
+            400 => "Bad Request",
+            401 => "Unauthorized",
+            403 => "Forbidden",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            _ => "Internal Server Error",
+        };
+
+        let mut data = format!("HTTP/1.1 {} {reason}\r\n", self.status);
+
+        if !self.body.is_empty() {
+            data.push_str("Content-Type: application/json\r\n");
+        }
+        for (k, v) in &self.headers {
+            data.push_str(&format!("{k}: {v}\r\n"));
+        }
+        data.push_str(&format!("Content-Length: {}\r\n", self.body.len()));
+        data.push_str("Connection: close\r\n\r\n");
+        data.push_str(&self.body);
+
+        data.into_bytes()
+    }
+}
+
+#[test]
+fn test_read_request() {
+    let data = "GET /play/aHR0cHM6Ly9leGFtcGxlLmNvbS8/?quality=720p HTTP/1.1\r\nHost: 127.0.0.1:3210\r\nX-Mpv-Handler-Token: secret\r\n\r\n";
+    let request = read_request(&mut data.as_bytes()).unwrap();
+
+    assert_eq!(request.method, "GET");
+    assert_eq!(
+        request.path,
+        "/play/aHR0cHM6Ly9leGFtcGxlLmNvbS8/?quality=720p"
+    );
+    assert_eq!(request.header("host"), Some("127.0.0.1:3210"));
+    assert_eq!(request.header("x-mpv-handler-token"), Some("secret"));
+    assert_eq!(request.header("Origin"), None);
+
+    for data in [
+        "GET /\r\n\r\n",
+        "GET / HTTP/1.1\r\nHost\r\n\r\n",
+        "GET / HTTP/1.1\r\n",
+    ] {
+        assert!(matches!(
+            read_request(&mut data.as_bytes()),
+            Err(Error::IncorrectRequest(_))
+        ));
+    }
+}
+
+#[test]
+fn test_token_eq() {
+    assert!(token_eq("secret", "secret"));
+    assert!(!token_eq("secret", "secreT"));
+    assert!(!token_eq("secret", "secret1"));
+    assert!(!token_eq("", ""));
+}
+
+#[test]
+fn test_serve() {
+    let config: Config = toml::from_str(
+        r#"
+            serve_origins = ["https://*.youtube.com"]
+            serve_token = "secret"
+        "#,
+    )
+    .unwrap();
+
+    // Fake runner, parse the link without running it
+    fn run(link: &str) -> Result<String, Error> {
+        Ok(crate::protocol::Protocol::parse(link, &Config::default())?.url)
+    }
+
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+    let port = listener.local_addr().unwrap().port();
+    std::thread::spawn(move || serve(listener, Arc::new(config), run));
+
+    let request = |method: &str, path: &str, headers: &[(&str, String)]| -> String {
+        let mut stream = TcpStream::connect((Ipv4Addr::LOCALHOST, port)).unwrap();
+        let mut data = format!("{method} {path} HTTP/1.1\r\n");
+        for (k, v) in headers {
+            data.push_str(&format!("{k}: {v}\r\n"));
+        }
+        data.push_str("\r\n");
+        stream.write_all(data.as_bytes()).unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    };
+
+    let path = "/play/aHR0cHM6Ly93d3cueW91dHViZS5jb20vd2F0Y2g_dj1HZ2tuMmY1ZS1JVQ/?quality=720p";
+    let host = ("Host", format!("127.0.0.1:{port}"));
+    let origin = ("Origin", "https://www.youtube.com".to_string());
+    let token = (TOKEN_HEADER, "secret".to_string());
+
+    let r = request("GET", path, &[host.clone(), origin.clone(), token.clone()]);
+    assert!(r.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(r.contains("Access-Control-Allow-Origin: https://www.youtube.com\r\n"));
+    assert!(r.ends_with(r#"{"ok":true,"url":"https://www.youtube.com/watch?v=Ggkn2f5e-IU"}"#));
+
+    // Without origin (not from web page)
+    let r = request("GET", path, &[host.clone(), token.clone()]);
+    assert!(r.starts_with("HTTP/1.1 200 OK\r\n"));
+
+    // Preflight
+    let r = request("OPTIONS", path, &[host.clone(), origin.clone()]);
+    assert!(r.starts_with("HTTP/1.1 204 No Content\r\n"));
+    assert!(r.contains("Access-Control-Allow-Headers: X-Mpv-Handler-Token\r\n"));
+
+    // Incorrect token, origin, host and link
+    let wrong = (TOKEN_HEADER, "wrong".to_string());
+    let r = request("GET", path, &[host.clone(), origin.clone(), wrong]);
+    assert!(r.starts_with("HTTP/1.1 401 Unauthorized\r\n"));
+
+    let evil = ("Origin", "https://evil.example.com".to_string());
+    let r = request("GET", path, &[host.clone(), evil, token.clone()]);
+    assert!(r.starts_with("HTTP/1.1 403 Forbidden\r\n"));
+    assert!(!r.contains("Access-Control-Allow-Origin"));
+
+    let rebind = ("Host", format!("evil.example.com:{port}"));
+    let r = request("GET", path, &[rebind, token.clone()]);
+    assert!(r.starts_with("HTTP/1.1 403 Forbidden\r\n"));
+
+    let r = request(
+        "GET",
+        "/play/ZmlsZTovLy9ldGMvcGFzc3dk/",
+        &[host.clone(), token.clone()],
+    );
+    assert!(r.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+    assert!(r.contains("Dangerous video protocol"));
+
+    let r = request("POST", path, &[host.clone(), token.clone()]);
+    assert!(r.starts_with("HTTP/1.1 405 Method Not Allowed\r\n"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_serve_detached() {
+    let config: Config = toml::from_str(r#"serve_token = "secret""#).unwrap();
+
+    // Runner starts a long-running process, as `crate::native::run` starts mpv-handler
+    fn run(link: &str) -> Result<String, Error> {
+        let url = crate::protocol::Protocol::parse(link, &Config::default())?.url;
+        crate::native::spawn(std::process::Command::new("sleep").arg("5"))?;
+        Ok(url)
+    }
+
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+    let port = listener.local_addr().unwrap().port();
+    std::thread::spawn(move || serve(listener, Arc::new(config), run));
+
+    let time = std::time::Instant::now();
+    let mut stream = TcpStream::connect((Ipv4Addr::LOCALHOST, port)).unwrap();
+    let data = format!(
+        "GET /play/aHR0cHM6Ly9leGFtcGxlLmNvbS8/ HTTP/1.1\r\nHost: 127.0.0.1:{port}\r\n{TOKEN_HEADER}: secret\r\n\r\n"
+    );
+    stream.write_all(data.as_bytes()).unwrap();
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(time.elapsed() < std::time::Duration::from_secs(2));
+}
@@ -6,12 +6,88 @@ use std::path::PathBuf;
 ///
 /// - `mpv`: mpv binary path
 /// - `ytdl`: yt-dlp binary path
+/// - `player`: player name (`mpv`, `vlc`, `celluloid`)
+/// - `vlc`: VLC binary path
+/// - `celluloid`: Celluloid binary path
+/// - `resolve`: resolve video URL by yt-dlp before starting player
 /// - `proxy: HTTP(S) proxy server address
-#[derive(Debug, Deserialize)]
+/// - `ipc_server`: mpv JSON IPC server socket path
+/// - `download_dir`: yt-dlp download directory path
+/// - `protocols`: allowed video URL protocols (narrows the safe protocols)
+/// - `allowed_hosts`: allowed video URL host glob patterns
+/// - `blocked_hosts`: blocked video URL host glob patterns
+/// - `legacy_schemes`: accept deprecated schemes `mpv://` and `mpv-debug://`
+/// - `sign_secret`: HMAC secret of signed links, links must be signed if set
+/// - `confirm`: ask user before running plugins
+/// - `confirm_command`: external command to ask user
+/// - `notify_command`: external command to notify user of errors
+/// - `serve_port`: loopback port of `serve` command
+/// - `serve_origins`: allowed `Origin` glob patterns of `serve` command
+/// - `serve_token`: shared token of `serve` command
+/// - `log_level`: log level (`off`, `error`, `warn`, `info`, `debug`)
+/// - `log_max_size`: max size of log file in bytes before rotation
+/// - `history`: record playback history (default: `true`)
+/// - `site`: per-site rules
+#[derive(Debug, Default, Deserialize)]
 pub struct Config {
     pub mpv: Option<String>,
     pub ytdl: Option<String>,
+    pub player: Option<String>,
+    pub vlc: Option<String>,
+    pub celluloid: Option<String>,
+    #[serde(default)]
+    pub resolve: bool,
     pub proxy: Option<String>,
+    pub ipc_server: Option<String>,
+    pub download_dir: Option<String>,
+    pub protocols: Option<Vec<String>>,
+    #[serde(default)]
+    pub allowed_hosts: Vec<String>,
+    #[serde(default)]
+    pub blocked_hosts: Vec<String>,
+    #[serde(default)]
+    pub legacy_schemes: bool,
+    pub sign_secret: Option<String>,
+    #[serde(default)]
+    pub confirm: bool,
+    pub confirm_command: Option<Vec<String>>,
+    pub notify_command: Option<Vec<String>>,
+    pub serve_port: Option<u16>,
+    #[serde(default)]
+    pub serve_origins: Vec<String>,
+    pub serve_token: Option<String>,
+    pub log_level: Option<String>,
+    pub log_max_size: Option<u64>,
+    pub history: Option<bool>,
+    #[serde(default)]
+    pub site: Vec<Site>,
+}
+
+/// Site rule of mpv-handler
+///
+/// Matched by the host glob pattern and (or) the regex of whole URL,
+/// and applies default parameters to the video of this site.
+///
+/// - `host`: host glob pattern, e.g. `*.twitch.tv`
+/// - `regex`: regex of video URL
+/// - `profile`, `quality`, `v_codec`, `cookies`: default parameters
+/// - `proxy`: HTTP(S) proxy server address
+/// - `player`: player name
+/// - `mpv_args`: extra mpv options
+#[derive(Debug, Deserialize)]
+pub struct Site {
+    pub host: Option<String>,
+    pub regex: Option<String>,
+    #[serde(skip)]
+    compiled: Option<regex::Regex>,
+    pub profile: Option<String>,
+    pub quality: Option<String>,
+    pub v_codec: Option<String>,
+    pub cookies: Option<String>,
+    pub proxy: Option<String>,
+    pub player: Option<String>,
+    #[serde(default)]
+    pub mpv_args: Vec<String>,
 }
 
 impl Config {
@@ -24,7 +100,7 @@ impl Config {
 
             if path.exists() {
                 let data: String = std::fs::read_to_string(&path)?;
-                let mut config: Config = toml::from_str(&data)?;
+                let mut config = Config::parse(&data)?;
 
                 if let Some(mpv) = config.mpv {
                     config.mpv = Some(realpath(mpv)?);
@@ -32,15 +108,133 @@ impl Config {
                 if let Some(ytdl) = config.ytdl {
                     config.ytdl = Some(realpath(ytdl)?);
                 }
+                if let Some(vlc) = config.vlc {
+                    config.vlc = Some(realpath(vlc)?);
+                }
+                if let Some(celluloid) = config.celluloid {
+                    config.celluloid = Some(realpath(celluloid)?);
+                }
 
                 return Ok(config);
             }
         }
 
-        Ok(default_config())
+        Ok(Config::default())
+    }
+
+    /// Parse config from TOML string and compile the regex of site rules
+    pub fn parse(data: &str) -> Result<Config, Error> {
+        let mut config: Config = toml::from_str(data)?;
+
+        for name in config
+            .site
+            .iter()
+            .map(|v| &v.player)
+            .chain([&config.player])
+        {
+            if let Some(name) = name
+                && crate::players::from_name(name).is_none()
+            {
+                return Err(Error::IncorrectPlayer(name.to_string()));
+            }
+        }
+        for site in &mut config.site {
+            if let Some(regex) = &site.regex {
+                site.compiled = Some(regex::Regex::new(regex).map_err(Error::IncorrectSiteRegex)?);
+            }
+        }
+
+        Ok(config)
+    }
+
+    /// Returns the first site rule matched by the given URL
+    pub fn site(&self, url: &str) -> Option<&Site> {
+        self.site.iter().find(|site| site.matches(url))
+    }
+
+    /// Returns HTTP(S) proxy server address for the given URL
+    pub fn proxy(&self, url: &str) -> Option<&str> {
+        match self.site(url).and_then(|site| site.proxy.as_deref()) {
+            Some(v) => Some(v),
+            None => self.proxy.as_deref(),
+        }
+    }
+
+    /// Returns player name for the given URL, `mpv` by default
+    pub fn player(&self, url: &str) -> &str {
+        match self.site(url).and_then(|site| site.player.as_deref()) {
+            Some(v) => v,
+            None => self.player.as_deref().unwrap_or("mpv"),
+        }
+    }
+
+    /// Returns extra mpv options for the given URL
+    pub fn mpv_args(&self, url: &str) -> &[String] {
+        match self.site(url) {
+            Some(site) => &site.mpv_args,
+            None => &[],
+        }
     }
 }
 
+impl Site {
+    /// Check the site rule matches the given URL
+    ///
+    /// A rule without `host` and `regex` matches nothing,
+    /// a rule with `regex` matches nothing until compiled by `Config::parse`
+    pub fn matches(&self, url: &str) -> bool {
+        if self.host.is_none() && self.regex.is_none() {
+            return false;
+        }
+
+        if let Some(host) = &self.host {
+            match crate::protocol::url_host(url) {
+                Some(v) if glob_match(host, v) => {}
+                _ => return false,
+            }
+        }
+
+        if self.regex.is_some() {
+            match &self.compiled {
+                Some(v) if v.is_match(url) => {}
+                _ => return false,
+            }
+        }
+
+        true
+    }
+}
+
+/// Match text with glob pattern (ASCII case-insensitive)
+///
+/// - `*`: matches any characters
+/// - `?`: matches one character
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let p: Vec<char> = pattern.to_ascii_lowercase().chars().collect();
+    let t: Vec<char> = text.to_ascii_lowercase().chars().collect();
+    let (mut i, mut j) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+
+    while j < t.len() {
+        if i < p.len() && (p[i] == '?' || p[i] == t[j]) {
+            i += 1;
+            j += 1;
+        } else if i < p.len() && p[i] == '*' {
+            star = Some((i, j));
+            i += 1;
+        } else if let Some((si, sj)) = star {
+            // Backtrack, let the last star match one more character
+            i = si + 1;
+            j = sj + 1;
+            star = Some((si, sj + 1));
+        } else {
+            return false;
+        }
+    }
+
+    p[i..].iter().all(|c| *c == '*')
+}
+
 /// Returns config directory path of mpv-handler
 pub fn get_config_dir() -> Option<PathBuf> {
     // Linux config directory location: $XDG_CONFIG_HOME/mpv-handler/
@@ -65,6 +259,17 @@ pub fn get_config_dir() -> Option<PathBuf> {
     None
 }
 
+/// Returns state directory path of mpv-handler (logs and history)
+///
+/// - Linux: `$XDG_STATE_HOME/mpv-handler/`
+/// - Windows: config directory
+pub fn get_state_dir() -> Option<PathBuf> {
+    match dirs::state_dir() {
+        Some(v) => Some(v.join("mpv-handler")),
+        None => get_config_dir(),
+    }
+}
+
 /// The default value of `Config.mpv`
 pub fn default_mpv() -> Result<String, Error> {
     #[cfg(unix)]
@@ -73,16 +278,41 @@ pub fn default_mpv() -> Result<String, Error> {
     return realpath("mpv.com");
 }
 
-/// The defalut value of `Config`
-fn default_config() -> Config {
-    Config {
-        mpv: None,
-        ytdl: None,
-        proxy: None,
+/// The default value of `Config.ytdl`
+pub fn default_ytdl() -> Result<String, Error> {
+    #[cfg(unix)]
+    return realpath("yt-dlp");
+    #[cfg(windows)]
+    return realpath("yt-dlp.exe");
+}
+
+/// The default value of `Config.vlc`
+pub fn default_vlc() -> Result<String, Error> {
+    #[cfg(unix)]
+    return realpath("vlc");
+    #[cfg(windows)]
+    return realpath("vlc.exe");
+}
+
+/// The default value of `Config.celluloid`
+pub fn default_celluloid() -> Result<String, Error> {
+    #[cfg(unix)]
+    return realpath("celluloid");
+    #[cfg(windows)]
+    return realpath("celluloid.exe");
+}
+
+/// Returns download directory path
+///
+/// If `Config.download_dir` doesn't set, returns user's download directory
+pub fn download_dir(config: &Config) -> Option<String> {
+    match &config.download_dir {
+        Some(v) => Some(v.clone()),
+        None => dirs::download_dir().map(|v| v.display().to_string()),
     }
 }
 
-fn realpath<T: AsRef<std::ffi::OsStr>>(path: T) -> Result<String, Error> {
+pub fn realpath<T: AsRef<std::ffi::OsStr>>(path: T) -> Result<String, Error> {
     let path = std::path::PathBuf::from(&path);
 
     if path.is_relative() {
@@ -116,14 +346,50 @@ fn test_config_parse() {
         r#"
             mpv = "/usr/bin/mpv"
             ytdl = "/usr/bin/yt-dlp"
+            player = "vlc"
+            vlc = "/usr/bin/vlc"
+            celluloid = "/usr/bin/celluloid"
+            resolve = true
             proxy = "http://example.com:8080"
+            ipc_server = "/tmp/mpvsocket"
+            download_dir = "/tmp/videos"
+            confirm = true
+            confirm_command = ["kdialog", "--yesno"]
+            notify_command = ["kdialog", "--passivepopup"]
+            serve_port = 8080
+            serve_origins = ["https://www.youtube.com"]
+            serve_token = "secret"
+            log_level = "debug"
+            log_max_size = 4096
+            history = false
         "#,
     )
     .unwrap();
 
     assert_eq!(config.mpv, Some("/usr/bin/mpv".to_string()));
     assert_eq!(config.ytdl, Some("/usr/bin/yt-dlp".to_string()));
+    assert_eq!(config.player, Some("vlc".to_string()));
+    assert_eq!(config.vlc, Some("/usr/bin/vlc".to_string()));
+    assert_eq!(config.celluloid, Some("/usr/bin/celluloid".to_string()));
+    assert!(config.resolve);
     assert_eq!(config.proxy, Some("http://example.com:8080".to_string()));
+    assert_eq!(config.ipc_server, Some("/tmp/mpvsocket".to_string()));
+    assert_eq!(config.download_dir, Some("/tmp/videos".to_string()));
+    assert!(config.confirm);
+    assert_eq!(
+        config.confirm_command,
+        Some(vec!["kdialog".to_string(), "--yesno".to_string()])
+    );
+    assert_eq!(
+        config.notify_command,
+        Some(vec!["kdialog".to_string(), "--passivepopup".to_string()])
+    );
+    assert_eq!(config.serve_port, Some(8080));
+    assert_eq!(config.serve_origins, ["https://www.youtube.com"]);
+    assert_eq!(config.serve_token, Some("secret".to_string()));
+    assert_eq!(config.log_level, Some("debug".to_string()));
+    assert_eq!(config.log_max_size, Some(4096));
+    assert_eq!(config.history, Some(false));
 
     // Unexpected values
     let config: Config = toml::from_str(
@@ -138,5 +404,98 @@ fn test_config_parse() {
     #[cfg(unix)]
     assert_eq!(config.mpv, None);
     assert_eq!(config.ytdl, None);
+    assert_eq!(config.player, None);
+    assert_eq!(config.vlc, None);
+    assert_eq!(config.celluloid, None);
+    assert!(!config.resolve);
     assert_eq!(config.proxy, None);
+    assert_eq!(config.ipc_server, None);
+    assert_eq!(config.download_dir, None);
+    assert_eq!(config.protocols, None);
+    assert!(config.allowed_hosts.is_empty());
+    assert!(config.blocked_hosts.is_empty());
+    assert!(!config.legacy_schemes);
+    assert_eq!(config.sign_secret, None);
+    assert!(!config.confirm);
+    assert_eq!(config.confirm_command, None);
+    assert_eq!(config.notify_command, None);
+    assert_eq!(config.serve_port, None);
+    assert!(config.serve_origins.is_empty());
+    assert_eq!(config.serve_token, None);
+    assert_eq!(config.log_level, None);
+    assert_eq!(config.log_max_size, None);
+    assert_eq!(config.history, None);
+    assert!(config.site.is_empty());
+}
+
+#[test]
+fn test_config_site() {
+    let config = Config::parse(
+        r#"
+            proxy = "http://example.com:8080"
+
+            [[site]]
+            host = "*.twitch.tv"
+            profile = "low-latency"
+            mpv_args = ["--no-video"]
+
+            [[site]]
+            regex = "^https://intranet\\.example\\.com/videos/"
+            cookies = "intranet.txt"
+            proxy = "http://proxy.intranet:3128"
+            player = "vlc"
+        "#,
+    )
+    .unwrap();
+
+    let site = config.site("https://www.twitch.tv/example").unwrap();
+    assert_eq!(site.profile, Some("low-latency".to_string()));
+    assert_eq!(
+        config.mpv_args("https://www.twitch.tv/example"),
+        ["--no-video"]
+    );
+    assert_eq!(
+        config.proxy("https://www.twitch.tv/example"),
+        Some("http://example.com:8080")
+    );
+
+    let site = config
+        .site("https://intranet.example.com/videos/1")
+        .unwrap();
+    assert_eq!(site.cookies, Some("intranet.txt".to_string()));
+    assert_eq!(
+        config.proxy("https://intranet.example.com/videos/1"),
+        Some("http://proxy.intranet:3128")
+    );
+    assert_eq!(
+        config.player("https://intranet.example.com/videos/1"),
+        "vlc"
+    );
+    assert_eq!(config.player("https://www.twitch.tv/example"), "mpv");
+
+    assert!(config.site("https://intranet.example.com/other").is_none());
+    assert!(config.site("https://twitch.tv.example.com/").is_none());
+}
+
+#[test]
+fn test_config_site_regex() {
+    let config = Config::parse("[[site]]\nregex = \"^https://example\\\\.com/\"").unwrap();
+    assert!(config.site("https://example.com/video").is_some());
+    assert!(config.site("https://example.org/video").is_none());
+
+    assert!(matches!(
+        Config::parse("[[site]]\nregex = \"(\""),
+        Err(Error::IncorrectSiteRegex(_))
+    ));
+}
+
+#[test]
+fn test_glob_match() {
+    assert!(glob_match("*.twitch.tv", "www.twitch.tv"));
+    assert!(glob_match("*.twitch.tv", "WWW.Twitch.TV"));
+    assert!(glob_match("*", "example.com"));
+    assert!(glob_match("example.co?", "example.com"));
+    assert!(glob_match("*.*.example.com", "a.b.example.com"));
+    assert!(!glob_match("*.twitch.tv", "twitch.tv"));
+    assert!(!glob_match("example.com", "www.example.com"));
 }
@@ -0,0 +1,279 @@
+use crate::config::Config;
+use crate::error::Error;
+use crate::protocol::{Protocol, Schemes};
+use std::path::{Path, PathBuf};
+
+/// Answer of confirmation
+#[derive(Debug, PartialEq)]
+enum Answer {
+    Yes,
+    No,
+    Always,
+}
+
+/// Ask user before running the plugin (if `Config.confirm` is enabled)
+///
+/// The prompt shows the video URL and options, it is asked in terminal for
+/// scheme `mpv-handler-debug`, otherwise by `Config.confirm_command`.
+/// The hosts always allowed by user are saved to `trusted_hosts` in config directory,
+/// it is only skipped if all hosts of videos, subtitle and referrer are trusted.
+pub fn confirm(proto: &Protocol, config: &Config, options: &[String]) -> Result<(), Error> {
+    if !config.confirm {
+        return Ok(());
+    }
+
+    let urls = proto.urls();
+    let hosts = hosts(proto);
+    let trusted = trusted_hosts_path();
+
+    if let Some(p) = &trusted
+        && hosts.iter().all(|v| is_trusted(p, v))
+    {
+        return Ok(());
+    }
+
+    let message = message(&urls, options);
+    let answer = match proto.scheme {
+        Schemes::MpvHandlerDebug => ask_terminal(&message)?,
+        Schemes::MpvHandler => match &config.confirm_command {
+            Some(v) => ask_command(v, &message)?,
+            None => ask_command(&default_command(), &message)?,
+        },
+    };
+
+    match answer {
+        Answer::Yes => Ok(()),
+        Answer::Always => {
+            if let Some(p) = &trusted {
+                for host in hosts.iter().filter(|v| !is_trusted(p, v)) {
+                    remember(p, host)?;
+                }
+            }
+            Ok(())
+        }
+        Answer::No => Err(Error::UserRejected(proto.url.clone())),
+    }
+}
+
+/// Returns the hosts of video URLs, subtitle and referrer
+fn hosts<'a>(proto: &'a Protocol) -> Vec<&'a str> {
+    let mut hosts: Vec<&str> = Vec::new();
+    let urls = proto
+        .urls()
+        .into_iter()
+        .chain(proto.subfile.as_deref())
+        .chain(proto.referrer.as_deref());
+
+    for host in urls.map(|v| crate::protocol::url_host(v).unwrap_or_default()) {
+        if !hosts.contains(&host) {
+            hosts.push(host);
+        }
+    }
+
+    hosts
+}
+
+/// Returns the path of trusted hosts list
+fn trusted_hosts_path() -> Option<PathBuf> {
+    crate::config::get_config_dir().map(|mut p| {
+        p.push("trusted_hosts");
+        p
+    })
+}
+
+/// Check the host is in trusted hosts list (one host per line)
+fn is_trusted(path: &Path, host: &str) -> bool {
+    match std::fs::read_to_string(path) {
+        Ok(data) => !host.is_empty() && data.lines().any(|v| v.trim().eq_ignore_ascii_case(host)),
+        Err(_) => false,
+    }
+}
+
+/// Append the host to trusted hosts list
+fn remember(path: &Path, host: &str) -> Result<(), Error> {
+    use std::io::Write;
+
+    if host.is_empty() {
+        return Ok(());
+    }
+
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    writeln!(file, "{host}")?;
+
+    Ok(())
+}
+
+/// Returns the prompt message
+fn message(urls: &[&str], options: &[String]) -> String {
+    let mut message = match urls.len() {
+        1 => "Open this video with mpv-handler?\n\n".to_string(),
+        n => format!("Open these {n} videos with mpv-handler?\n\n"),
+    };
+
+    for url in urls {
+        message.push_str(&format!("{url}\n"));
+    }
+
+    if !options.is_empty() {
+        message.push_str("\nOptions:\n");
+        for option in options {
+            message.push_str(&format!("    {option}\n"));
+        }
+    }
+
+    message
+}
+
+/// The default value of `Config.confirm_command`
+fn default_command() -> Vec<String> {
+    [
+        "zenity",
+        "--question",
+        "--no-markup",
+        "--extra-button=Always",
+        "--text",
+    ]
+    .iter()
+    .map(|v| v.to_string())
+    .collect()
+}
+
+/// Ask user in terminal, the prompt is printed to stderr to keep stdout for `--json`
+fn ask_terminal(message: &str) -> Result<Answer, Error> {
+    eprint!("{message}\n[y]es / [n]o / [a]lways allow this host: ");
+    std::io::Write::flush(&mut std::io::stderr())?;
+
+    let mut line = String::new();
+    std::io::stdin().read_line(&mut line)?;
+
+    Ok(parse_answer(&line))
+}
+
+/// Ask user by external command, the message is appended as the last argument
+///
+/// - Exit with success: allow once
+/// - Print `always` to stdout: always allow this host (e.g. zenity `--extra-button`)
+/// - Others: reject
+fn ask_command(command: &[String], message: &str) -> Result<Answer, Error> {
+    let (program, args) = match command.split_first() {
+        Some(v) => v,
+        None => {
+            let e = std::io::Error::new(std::io::ErrorKind::InvalidInput, "empty command");
+            return Err(Error::ConfirmRunFailed(e));
+        }
+    };
+
+    let output = std::process::Command::new(program)
+        .args(args)
+        .arg(message)
+        .output()
+        .map_err(Error::ConfirmRunFailed)?;
+
+    if String::from_utf8_lossy(&output.stdout)
+        .trim()
+        .eq_ignore_ascii_case("always")
+    {
+        return Ok(Answer::Always);
+    }
+
+    match output.status.success() {
+        true => Ok(Answer::Yes),
+        false => Ok(Answer::No),
+    }
+}
+
+/// Parse answer of terminal prompt
+fn parse_answer(answer: &str) -> Answer {
+    match answer.trim().to_ascii_lowercase().as_str() {
+        "y" | "yes" => Answer::Yes,
+        "a" | "always" => Answer::Always,
+        _ => Answer::No,
+    }
+}
+
+#[test]
+fn test_parse_answer() {
+    assert_eq!(parse_answer("y\n"), Answer::Yes);
+    assert_eq!(parse_answer("Always\n"), Answer::Always);
+    assert_eq!(parse_answer("\n"), Answer::No);
+    assert_eq!(parse_answer("whatever"), Answer::No);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_ask_command() {
+    let command = crate::testing::strings;
+
+    let yes = command(&["sh", "-c", "exit 0", "sh"]);
+    assert_eq!(ask_command(&yes, "message").unwrap(), Answer::Yes);
+
+    let no = command(&["sh", "-c", "exit 1", "sh"]);
+    assert_eq!(ask_command(&no, "message").unwrap(), Answer::No);
+
+    let always = command(&["sh", "-c", "echo Always; exit 1", "sh"]);
+    assert_eq!(ask_command(&always, "message").unwrap(), Answer::Always);
+
+    // The message is the last argument
+    let check = command(&["sh", "-c", "test \"$1\" = message", "sh"]);
+    assert_eq!(ask_command(&check, "message").unwrap(), Answer::Yes);
+
+    let missing = command(&["/nonexistent/mpv-handler-confirm"]);
+    assert!(matches!(
+        ask_command(&missing, "message"),
+        Err(Error::ConfirmRunFailed(_))
+    ));
+}
+
+#[test]
+fn test_trusted_hosts() {
+    let path = crate::testing::temp_path("trusted").join("trusted_hosts");
+
+    assert!(!is_trusted(&path, "www.youtube.com"));
+
+    remember(&path, "www.youtube.com").unwrap();
+    remember(&path, "www.twitch.tv").unwrap();
+
+    assert!(is_trusted(&path, "www.youtube.com"));
+    assert!(is_trusted(&path, "WWW.Twitch.TV"));
+    assert!(!is_trusted(&path, "youtube.com"));
+    assert!(!is_trusted(&path, ""));
+
+    std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn test_message() {
+    let m = message(
+        &["https://example.com/"],
+        &["--profile=low-latency".to_string()],
+    );
+    assert!(m.contains("https://example.com/\n"));
+    assert!(m.contains("    --profile=low-latency\n"));
+
+    let m = message(&["https://example.com/", "https://example.org/"], &[]);
+    assert!(m.starts_with("Open these 2 videos"));
+    assert!(m.contains("https://example.com/\nhttps://example.org/\n"));
+}
+
+#[test]
+fn test_hosts() {
+    let config = Config::default();
+
+    // https://example.com/, subfile=https://sub.example.org/a.srt, referrer=https://example.com/
+    let proto = Protocol::parse(
+        "mpv-handler://play/aHR0cHM6Ly9leGFtcGxlLmNvbS8/?subfile=aHR0cHM6Ly9zdWIuZXhhbXBsZS5vcmcvYS5zcnQ&referrer=aHR0cHM6Ly9leGFtcGxlLmNvbS8",
+        &config,
+    )
+    .unwrap();
+    assert_eq!(hosts(&proto), ["example.com", "sub.example.org"]);
+
+    let proto = Protocol::parse("mpv-handler://play/aHR0cHM6Ly9leGFtcGxlLmNvbS8", &config).unwrap();
+    assert_eq!(hosts(&proto), ["example.com"]);
+}
@@ -1,19 +1,79 @@
 use thiserror::Error;
 
 #[derive(Debug, Error)]
+#[allow(clippy::enum_variant_names)]
 pub enum Error {
-    #[error("Too many arguments")]
-    TooManyArgs,
+    #[error("Incorrect argument \"{0}\"")]
+    IncorrectArgument(String),
+    #[error("Missing argument \"{0}\"")]
+    MissingArgument(String),
     #[error("Incorrect protocol \"{0}\"")]
     IncorrectProtocol(String),
     #[error("Incorrect video URL \"{0}\"")]
     IncorrectVideoURL(String),
     #[error("Dangerous video protocol \"{0}\"")]
     DangerousVideoProtocol(String),
-    #[error("Player exited by error")]
+    #[error("Blocked video host \"{0}\"")]
+    BlockedVideoHost(String),
+    #[error("Incorrect cookies file name \"{0}\"")]
+    IncorrectCookies(String),
+    #[error("Incorrect start position \"{0}\"")]
+    IncorrectStartAt(String),
+    #[error("Incorrect video quality \"{0}\"")]
+    IncorrectQuality(String),
+    #[error("Incorrect video codec \"{0}\"")]
+    IncorrectVideoCodec(String),
+    #[error("Incorrect profile name \"{0}\"")]
+    IncorrectProfile(String),
+    #[error("Incorrect playlist parameter \"{0}\"")]
+    IncorrectPlaylist(String),
+    #[error("Missing link signature")]
+    MissingSignature,
+    #[error("Incorrect link signature")]
+    IncorrectSignature,
+    #[error("Link expired (exp={0})")]
+    ExpiredLink(u64),
+    #[error("Player exited by error ({0})")]
     PlayerExited(u8),
     #[error("Failed to run player ({0})")]
     PlayerRunFailed(std::io::Error),
+    #[error("Downloader exited by error ({0})")]
+    DownloaderExited(u8),
+    #[error("Failed to run downloader ({0})")]
+    DownloaderRunFailed(std::io::Error),
+    #[error("Failed to run yt-dlp ({0})")]
+    ResolveRunFailed(std::io::Error),
+    #[error("Failed to resolve video URL ({0})")]
+    ResolveFailed(String),
+    #[error("Rejected by user \"{0}\"")]
+    UserRejected(String),
+    #[error("Failed to run confirmation command ({0})")]
+    ConfirmRunFailed(std::io::Error),
+    #[error("Failed to communicate with player ({0})")]
+    PlayerIpcFailed(String),
+    #[error("Incorrect native message ({0})")]
+    IncorrectNativeMessage(String),
+    #[cfg(windows)]
+    #[error("Failed to run link ({0})")]
+    NativeRunFailed(String),
+    #[error("Incorrect HTTP request ({0})")]
+    IncorrectRequest(String),
+    #[error("Missing config \"{0}\"")]
+    MissingConfig(String),
+    #[error("Doctor found {0} failed checks")]
+    DoctorFailed(usize),
+    #[error("Doctor found {0} warnings")]
+    DoctorWarned(usize),
+    #[error("Incorrect log level \"{0}\"")]
+    IncorrectLogLevel(String),
+    #[error("Incorrect player \"{0}\"")]
+    IncorrectPlayer(String),
+    #[error("History entry {0} not found")]
+    MissingHistoryEntry(u64),
+    #[error("History entry {0} can't be replayed, its secrets are redacted")]
+    RedactedHistoryEntry(u64),
+    #[error("Incorrect site rule regex ({0})")]
+    IncorrectSiteRegex(regex::Error),
     #[error("Failed to decode ({0})")]
     FromBase64Error(#[from] base64::DecodeError),
     #[error("Failed to decode ({0})")]
@@ -21,5 +81,158 @@ pub enum Error {
     #[error("Failed to decode ({0})")]
     FromTomlError(#[from] toml::de::Error),
     #[error("Failed to decode ({0})")]
+    FromJsonError(#[from] serde_json::Error),
+    #[error("Failed to decode ({0})")]
     FromIoError(#[from] std::io::Error),
 }
+
+impl Error {
+    /// Returns the stable exit code of error
+    ///
+    /// - `1`, `2`: doctor found failed checks or only warnings
+    /// - `10..`: arguments
+    /// - `20..`: link and parameters
+    /// - `40..`: player and downloader
+    /// - `50..`: confirmation
+    /// - `60..`: native messaging and HTTP bridge
+    /// - `70..`: config and local files
+    /// - `80..`: decoding and I/O
+    pub fn code(&self) -> u8 {
+        match self {
+            Error::DoctorFailed(_) => 1,
+            Error::DoctorWarned(_) => 2,
+            Error::IncorrectArgument(_) => 11,
+            Error::MissingArgument(_) => 12,
+            Error::IncorrectProtocol(_) => 20,
+            Error::IncorrectVideoURL(_) => 21,
+            Error::DangerousVideoProtocol(_) => 22,
+            Error::BlockedVideoHost(_) => 23,
+            Error::IncorrectCookies(_) => 24,
+            Error::IncorrectStartAt(_) => 25,
+            Error::IncorrectQuality(_) => 26,
+            Error::IncorrectVideoCodec(_) => 27,
+            Error::IncorrectProfile(_) => 28,
+            Error::IncorrectPlaylist(_) => 29,
+            Error::MissingSignature => 30,
+            Error::IncorrectSignature => 31,
+            Error::ExpiredLink(_) => 32,
+            Error::PlayerExited(_) => 40,
+            Error::PlayerRunFailed(_) => 41,
+            Error::DownloaderExited(_) => 42,
+            Error::DownloaderRunFailed(_) => 43,
+            Error::PlayerIpcFailed(_) => 44,
+            Error::ResolveRunFailed(_) => 45,
+            Error::ResolveFailed(_) => 46,
+            Error::UserRejected(_) => 50,
+            Error::ConfirmRunFailed(_) => 51,
+            Error::IncorrectNativeMessage(_) => 60,
+            #[cfg(windows)]
+            Error::NativeRunFailed(_) => 61,
+            Error::IncorrectRequest(_) => 62,
+            Error::MissingConfig(_) => 70,
+            Error::IncorrectLogLevel(_) => 71,
+            Error::IncorrectSiteRegex(_) => 72,
+            Error::MissingHistoryEntry(_) => 73,
+            Error::IncorrectPlayer(_) => 74,
+            Error::RedactedHistoryEntry(_) => 75,
+            Error::FromBase64Error(_) => 80,
+            Error::FromStringError(_) => 81,
+            Error::FromTomlError(_) => 82,
+            Error::FromJsonError(_) => 83,
+            Error::FromIoError(_) => 84,
+        }
+    }
+
+    /// Returns the stable string ID of error
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Error::IncorrectArgument(_) => "incorrect_argument",
+            Error::MissingArgument(_) => "missing_argument",
+            Error::IncorrectProtocol(_) => "incorrect_protocol",
+            Error::IncorrectVideoURL(_) => "incorrect_video_url",
+            Error::DangerousVideoProtocol(_) => "dangerous_video_protocol",
+            Error::BlockedVideoHost(_) => "blocked_video_host",
+            Error::IncorrectCookies(_) => "incorrect_cookies",
+            Error::IncorrectStartAt(_) => "incorrect_startat",
+            Error::IncorrectQuality(_) => "incorrect_quality",
+            Error::IncorrectVideoCodec(_) => "incorrect_v_codec",
+            Error::IncorrectProfile(_) => "incorrect_profile",
+            Error::IncorrectPlaylist(_) => "incorrect_playlist",
+            Error::MissingSignature => "missing_signature",
+            Error::IncorrectSignature => "incorrect_signature",
+            Error::ExpiredLink(_) => "expired_link",
+            Error::PlayerExited(_) => "player_exited",
+            Error::PlayerRunFailed(_) => "player_run_failed",
+            Error::DownloaderExited(_) => "downloader_exited",
+            Error::DownloaderRunFailed(_) => "downloader_run_failed",
+            Error::ResolveRunFailed(_) => "resolve_run_failed",
+            Error::ResolveFailed(_) => "resolve_failed",
+            Error::UserRejected(_) => "user_rejected",
+            Error::ConfirmRunFailed(_) => "confirm_run_failed",
+            Error::PlayerIpcFailed(_) => "player_ipc_failed",
+            Error::IncorrectNativeMessage(_) => "incorrect_native_message",
+            #[cfg(windows)]
+            Error::NativeRunFailed(_) => "native_run_failed",
+            Error::IncorrectRequest(_) => "incorrect_request",
+            Error::MissingConfig(_) => "missing_config",
+            Error::DoctorFailed(_) => "doctor_failed",
+            Error::DoctorWarned(_) => "doctor_warned",
+            Error::IncorrectLogLevel(_) => "incorrect_log_level",
+            Error::IncorrectSiteRegex(_) => "incorrect_site_regex",
+            Error::MissingHistoryEntry(_) => "missing_history_entry",
+            Error::IncorrectPlayer(_) => "incorrect_player",
+            Error::RedactedHistoryEntry(_) => "redacted_history_entry",
+            Error::FromBase64Error(_) => "decode_base64",
+            Error::FromStringError(_) => "decode_string",
+            Error::FromTomlError(_) => "decode_toml",
+            Error::FromJsonError(_) => "decode_json",
+            Error::FromIoError(_) => "io",
+        }
+    }
+
+    /// Returns exit code of child process, if it exited by error
+    pub fn exit_code(&self) -> Option<u8> {
+        match self {
+            Error::PlayerExited(code) | Error::DownloaderExited(code) => Some(*code),
+            _ => None,
+        }
+    }
+
+    /// Returns JSON output of error, `url` is the video URL if the link is parsed
+    pub fn json(&self, url: Option<&str>) -> serde_json::Value {
+        let mut json = serde_json::json!({
+            "ok": false,
+            "code": self.code(),
+            "kind": self.kind(),
+            "message": self.to_string(),
+            "url": url,
+        });
+
+        if let Some(code) = self.exit_code() {
+            json["exit_code"] = serde_json::Value::from(code);
+        }
+
+        json
+    }
+}
+
+#[test]
+fn test_error_json() {
+    let e = Error::DangerousVideoProtocol("file:///etc/passwd".to_string());
+    assert_eq!(
+        e.json(None).to_string(),
+        r#"{"ok":false,"code":22,"kind":"dangerous_video_protocol","message":"Dangerous video protocol \"file:///etc/passwd\"","url":null}"#
+    );
+
+    let e = Error::PlayerExited(4);
+    let json = e.json(Some("https://example.com/"));
+    assert_eq!(json["code"], 40);
+    assert_eq!(json["kind"], "player_exited");
+    assert_eq!(json["message"], "Player exited by error (4)");
+    assert_eq!(json["url"], "https://example.com/");
+    assert_eq!(json["exit_code"], 4);
+
+    let e = Error::DownloaderExited(2);
+    assert_eq!(e.to_string(), "Downloader exited by error (2)");
+    assert_eq!(e.json(None)["exit_code"], 2);
+}
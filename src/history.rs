@@ -0,0 +1,200 @@
+use crate::config::Config;
+use crate::error::Error;
+use crate::protocol::Protocol;
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+/// History file name
+const HISTORY_FILE: &str = "history.jsonl";
+
+/// Playback history entry, stored as a JSON line
+///
+/// - `id`: incremental entry ID
+/// - `time`: UTC time of playback
+/// - `url`: video URL
+/// - `title`: video title (parameter `v_title`)
+/// - `site`: video URL host
+/// - `status`: exit status of player
+/// - `link`: mpv-handler link to replay the video
+/// - `redacted`: the secrets in `url` and `link` are redacted (as in log), it can't be replayed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Entry {
+    pub id: u64,
+    pub time: String,
+    pub url: String,
+    pub title: Option<String>,
+    pub site: Option<String>,
+    pub status: u8,
+    pub link: String,
+    #[serde(default)]
+    pub redacted: bool,
+}
+
+impl Entry {
+    /// Returns entry of `proto`, the ID is set by `append()`
+    pub fn new(proto: &Protocol, status: u8) -> Entry {
+        let link = proto.encode();
+        let redacted = crate::log::redact_arg(&link);
+
+        Entry {
+            id: 0,
+            time: crate::log::timestamp(std::time::SystemTime::now()),
+            url: crate::log::redact(&proto.url),
+            title: proto.v_title.clone(),
+            site: crate::protocol::url_host(&proto.url).map(str::to_string),
+            status,
+            redacted: redacted != link,
+            link: redacted,
+        }
+    }
+
+    /// Check the URL, title or site contains `text` (case-insensitive)
+    pub fn matches(&self, text: &str) -> bool {
+        let text = text.to_lowercase();
+
+        [Some(&self.url), self.title.as_ref(), self.site.as_ref()]
+            .into_iter()
+            .flatten()
+            .any(|v| v.to_lowercase().contains(&text))
+    }
+}
+
+/// Returns history file path
+pub fn path() -> Option<PathBuf> {
+    crate::config::get_state_dir().map(|v| v.join(HISTORY_FILE))
+}
+
+/// Record playback of `proto`, unless it's disabled by `Config.history`
+pub fn record(proto: &Protocol, config: &Config, status: u8) {
+    if config.history == Some(false) {
+        return;
+    }
+
+    if let Some(path) = path() {
+        // History must not break the handler, only report it
+        if let Err(e) = append(&path, Entry::new(proto, status)) {
+            crate::log::warn("history", serde_json::json!({ "error": e.to_string() }));
+        }
+    }
+}
+
+/// Append `entry` to history file with the next ID, and returns the ID
+///
+/// The file is locked while appending, so concurrent handlers don't get the same ID.
+pub fn append(path: &Path, mut entry: Entry) -> Result<u64, Error> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .read(true)
+        .append(true)
+        .open(path)?;
+    file.lock()?;
+
+    let mut data = String::new();
+    file.read_to_string(&mut data)?;
+
+    entry.id = parse(&data).iter().map(|v| v.id).max().unwrap_or(0) + 1;
+    file.write_all((serde_json::to_string(&entry)? + "\n").as_bytes())?;
+
+    Ok(entry.id)
+}
+
+/// Load history entries, from oldest to newest
+///
+/// Returns empty entries if history file doesn't exists, broken lines are skipped.
+pub fn load(path: &Path) -> Result<Vec<Entry>, Error> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    Ok(parse(&std::fs::read_to_string(path)?))
+}
+
+/// Parse history entries of JSON lines, broken lines are skipped
+fn parse(data: &str) -> Vec<Entry> {
+    data.lines()
+        .filter_map(|v| serde_json::from_str(v).ok())
+        .collect()
+}
+
+/// Remove history file
+pub fn clear(path: &Path) -> Result<(), Error> {
+    if path.exists() {
+        std::fs::remove_file(path)?;
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_history() {
+    let dir = crate::testing::temp_path("history");
+    let path = dir.join(HISTORY_FILE);
+    let config = Config::default();
+
+    assert!(load(&path).unwrap().is_empty());
+
+    let link = "mpv-handler://play/aHR0cHM6Ly93d3cueW91dHViZS5jb20vd2F0Y2g_dj1HZ2tuMmY1ZS1JVQ/?v_title=TWFyYmxlIFNvZGEg";
+    let proto = Protocol::parse(link, &config).unwrap();
+    assert_eq!(append(&path, Entry::new(&proto, 0)).unwrap(), 1);
+
+    let link = "mpv-handler://play/aHR0cHM6Ly93d3cudHdpdGNoLnR2L2V4YW1wbGU/";
+    let proto = Protocol::parse(link, &config).unwrap();
+    assert_eq!(append(&path, Entry::new(&proto, 2)).unwrap(), 2);
+
+    // Broken lines are skipped
+    let mut file = std::fs::OpenOptions::new()
+        .append(true)
+        .open(&path)
+        .unwrap();
+    file.write_all(b"{broken\n").unwrap();
+
+    let entries = load(&path).unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(
+        entries[0].url,
+        "https://www.youtube.com/watch?v=Ggkn2f5e-IU"
+    );
+    assert_eq!(entries[0].title, Some("Marble Soda ".to_string()));
+    assert_eq!(entries[0].site, Some("www.youtube.com".to_string()));
+    assert_eq!(entries[1].status, 2);
+    assert_eq!(entries[1].link, link);
+
+    // Search
+    assert!(entries[0].matches("marble"));
+    assert!(entries[1].matches("TWITCH"));
+    assert!(!entries[1].matches("youtube"));
+
+    // Secrets are redacted, https://example.com/v.m3u8?token=abc
+    let link = "mpv-handler://play/aHR0cHM6Ly9leGFtcGxlLmNvbS92Lm0zdTg_dG9rZW49YWJj/";
+    let proto = Protocol::parse(link, &config).unwrap();
+    assert_eq!(append(&path, Entry::new(&proto, 0)).unwrap(), 3);
+
+    let entry = load(&path).unwrap().pop().unwrap();
+    assert_eq!(entry.url, "https://example.com/v.m3u8?token=REDACTED");
+    assert_eq!(entry.link, crate::log::redact_arg(link));
+    assert!(entry.redacted);
+    assert!(!load(&path).unwrap()[0].redacted);
+
+    // Concurrent appending gets unique IDs
+    let threads: Vec<_> = (0..8)
+        .map(|_| {
+            let path = path.clone();
+            let proto = Protocol::parse(link, &Config::default()).unwrap();
+            let entry = Entry::new(&proto, 0);
+            std::thread::spawn(move || append(&path, entry).unwrap())
+        })
+        .collect();
+    let mut ids: Vec<u64> = threads.into_iter().map(|v| v.join().unwrap()).collect();
+    ids.sort();
+    assert_eq!(ids, (4..12).collect::<Vec<u64>>());
+
+    clear(&path).unwrap();
+    assert!(load(&path).unwrap().is_empty());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
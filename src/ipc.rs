@@ -0,0 +1,339 @@
+use crate::error::Error;
+use serde_json::{Map, Value, json};
+use std::io::{BufRead, BufReader, Write};
+
+#[cfg(unix)]
+type Stream = std::os::unix::net::UnixStream;
+#[cfg(windows)]
+type Stream = std::fs::File;
+
+/// Timeout of reading and writing the socket
+const TIMEOUT: std::time::Duration = std::time::Duration::from_secs(3);
+/// List options of mpv which items are `key=value`
+const KEY_VALUE_LISTS: &[&str] = &["ytdl-raw-options", "script-opts", "demuxer-lavf-o"];
+
+/// Flags of mpv `loadfile` command
+#[derive(Debug, PartialEq)]
+pub enum LoadFlags {
+    /// Stop playback of the current file, and play the new file immediately
+    Replace,
+    /// Append the file to the playlist, and start playing if nothing is playing
+    AppendPlay,
+}
+
+impl LoadFlags {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LoadFlags::Replace => "replace",
+            LoadFlags::AppendPlay => "append-play",
+        }
+    }
+}
+
+/// JSON IPC client of a running mpv player
+///
+/// The server is created by mpv option `--input-ipc-server`
+pub struct Client {
+    reader: BufReader<Stream>,
+    writer: Stream,
+    request_id: u64,
+}
+
+impl Client {
+    /// Connect to mpv JSON IPC server
+    ///
+    /// If no player answers on the given socket, returns `None`
+    pub fn connect(socket: &str) -> Option<Client> {
+        Client::connect_timeout(socket, TIMEOUT)
+    }
+
+    /// Connect to mpv JSON IPC server, and check it replies in `timeout`
+    ///
+    /// The socket may be owned by other program which never replies,
+    /// then a new player should be started instead of hanging.
+    fn connect_timeout(socket: &str, timeout: std::time::Duration) -> Option<Client> {
+        #[cfg(unix)]
+        let stream = Stream::connect(socket).ok()?;
+        #[cfg(unix)]
+        {
+            stream.set_read_timeout(Some(timeout)).ok()?;
+            stream.set_write_timeout(Some(timeout)).ok()?;
+        }
+        // Named pipe has no timeout on Windows
+        #[cfg(windows)]
+        let _ = timeout;
+        #[cfg(windows)]
+        let stream = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(socket)
+            .ok()?;
+
+        let writer = stream.try_clone().ok()?;
+
+        let mut client = Client {
+            reader: BufReader::new(stream),
+            writer,
+            request_id: 0,
+        };
+
+        client
+            .command(json!(["get_property", "mpv-version"]))
+            .ok()?;
+
+        Some(client)
+    }
+
+    /// Send `loadfile` command with per-file options
+    pub fn loadfile<S: AsRef<str>>(
+        &mut self,
+        url: &str,
+        flags: LoadFlags,
+        options: &[S],
+    ) -> Result<(), Error> {
+        self.command(json!({
+            "name": "loadfile",
+            "url": url,
+            "flags": flags.as_str(),
+            "options": file_options(options),
+        }))
+    }
+
+    /// Send command and wait for the reply with the same `request_id`
+    fn command(&mut self, command: Value) -> Result<(), Error> {
+        self.request_id += 1;
+
+        let mut data = json!({ "command": command, "request_id": self.request_id }).to_string();
+        data.push('\n');
+
+        self.writer
+            .write_all(data.as_bytes())
+            .map_err(|e| Error::PlayerIpcFailed(e.to_string()))?;
+
+        loop {
+            let reply = self.read()?;
+
+            // Skip events and replies of other requests
+            if reply["request_id"].as_u64() != Some(self.request_id) {
+                continue;
+            }
+
+            return match reply["error"].as_str() {
+                Some("success") => Ok(()),
+                Some(e) => Err(Error::PlayerIpcFailed(e.to_string())),
+                None => Err(Error::PlayerIpcFailed(reply.to_string())),
+            };
+        }
+    }
+
+    /// Wait for the event with given name, e.g. `file-loaded`
+    pub fn wait_event(&mut self, name: &str) -> Result<(), Error> {
+        loop {
+            if self.read()?["event"].as_str() == Some(name) {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Read a message of the server
+    fn read(&mut self) -> Result<Value, Error> {
+        let mut line = String::new();
+
+        match self.reader.read_line(&mut line) {
+            Ok(0) => Err(Error::PlayerIpcFailed("connection closed".to_string())),
+            Ok(_) => Ok(serde_json::from_str(&line)?),
+            Err(e) => Err(Error::PlayerIpcFailed(e.to_string())),
+        }
+    }
+}
+
+/// Convert command line options to per-file options of `loadfile`
+///
+/// `--key=value` becomes `key: value`, flag `--key` becomes `key: yes` and
+/// negated flag `--no-key` becomes `key: no`. The values of list options
+/// `--key-append=value` are joined by comma, so the commas in items are quoted:
+///
+/// - Key-value list items (e.g. `format-sort=res:720,+vcodec:vp9`): the values
+///   are quoted as `%LENGTH%VALUE`
+/// - String list items (e.g. `Accept: text/html,*/*`): the commas are escaped
+///   by backslash, mpv doesn't support `%LENGTH%` quoting of string lists
+fn file_options<S: AsRef<str>>(options: &[S]) -> Map<String, Value> {
+    let mut map: Map<String, Value> = Map::new();
+
+    for option in options {
+        let option = option.as_ref().trim_start_matches("--");
+        let (k, v) = match option.split_once('=') {
+            Some(v) => v,
+            None => match option.strip_prefix("no-") {
+                Some(k) => (k, "no"),
+                None => (option, "yes"),
+            },
+        };
+
+        match k.strip_suffix("-append") {
+            Some(k) => {
+                let v = match v.split_once('=') {
+                    Some((item, value)) if KEY_VALUE_LISTS.contains(&k) => {
+                        format!("{item}=%{}%{value}", value.len())
+                    }
+                    _ => v.replace(',', "\\,"),
+                };
+
+                match map.get_mut(k) {
+                    Some(Value::String(s)) => {
+                        s.push(',');
+                        s.push_str(&v);
+                    }
+                    _ => {
+                        map.insert(k.to_string(), Value::from(v));
+                    }
+                }
+            }
+            None => {
+                map.insert(k.to_string(), Value::from(v));
+            }
+        }
+    }
+
+    map
+}
+
+#[cfg(unix)]
+#[cfg(test)]
+fn fake_server(name: &str, reply: &'static str) -> (String, std::thread::JoinHandle<String>) {
+    let socket = crate::testing::temp_path(name).with_extension("sock");
+    let _ = std::fs::remove_file(&socket);
+    let listener = std::os::unix::net::UnixListener::bind(&socket).unwrap();
+
+    let server = std::thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut request = String::new();
+
+        // Reply the check of `Client::connect`
+        reader.read_line(&mut request).unwrap();
+        stream
+            .write_all(b"{\"data\":\"mpv 0.40.0\",\"request_id\":1,\"error\":\"success\"}\n")
+            .unwrap();
+
+        request.clear();
+        reader.read_line(&mut request).unwrap();
+
+        // Events can arrive before the reply
+        stream.write_all(b"{\"event\":\"idle\"}\n").unwrap();
+        stream.write_all(reply.as_bytes()).unwrap();
+        request
+    });
+
+    (socket.display().to_string(), server)
+}
+
+#[test]
+fn test_file_options() {
+    let map = file_options(&[
+        "--profile=low-latency",
+        "--ytdl-raw-options-append=cookies=/tmp/cookies.txt",
+        "--ytdl-raw-options-append=format-sort=res:720,+vcodec:vp9",
+        "--http-header-fields-append=Referer: https://example.com/",
+        "--http-header-fields-append=Cookie: a=b,c",
+        "--http-header-fields-append=Accept: text/html,application/xhtml+xml",
+        "--ytdl=no",
+        "--no-video",
+        "--fullscreen",
+    ]);
+
+    assert_eq!(map["profile"], "low-latency");
+    assert_eq!(
+        map["ytdl-raw-options"],
+        "cookies=%16%/tmp/cookies.txt,format-sort=%19%res:720,+vcodec:vp9"
+    );
+    assert_eq!(
+        map["http-header-fields"],
+        r"Referer: https://example.com/,Cookie: a=b\,c,Accept: text/html\,application/xhtml+xml"
+    );
+    assert_eq!(map["ytdl"], "no");
+    assert_eq!(map["video"], "no");
+    assert_eq!(map["fullscreen"], "yes");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_ipc_not_running() {
+    let socket = std::env::temp_dir().join("mpv-handler-not-running.sock");
+    assert!(Client::connect(&socket.display().to_string()).is_none());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_ipc_not_replying() {
+    let socket = crate::testing::temp_path("silent").with_extension("sock");
+    let _ = std::fs::remove_file(&socket);
+    let listener = std::os::unix::net::UnixListener::bind(&socket).unwrap();
+
+    // Accept the connection but never reply
+    let server = std::thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(500));
+        drop(stream);
+    });
+
+    let timeout = std::time::Duration::from_millis(100);
+    assert!(Client::connect_timeout(&socket.display().to_string(), timeout).is_none());
+
+    server.join().unwrap();
+    std::fs::remove_file(&socket).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn test_ipc_loadfile() {
+    let (socket, server) = fake_server(
+        "loadfile",
+        "{\"data\":null,\"request_id\":2,\"error\":\"success\"}\n{\"event\":\"start-file\"}\n{\"event\":\"file-loaded\"}\n",
+    );
+
+    let mut client = Client::connect(&socket).unwrap();
+    client
+        .loadfile(
+            "https://example.com/",
+            LoadFlags::AppendPlay,
+            &["--profile=low-latency"],
+        )
+        .unwrap();
+    client.wait_event("file-loaded").unwrap();
+
+    let request: Value = serde_json::from_str(&server.join().unwrap()).unwrap();
+    assert_eq!(request["request_id"], 2);
+    assert_eq!(request["command"]["name"], "loadfile");
+    assert_eq!(request["command"]["url"], "https://example.com/");
+    assert_eq!(request["command"]["flags"], "append-play");
+    assert_eq!(request["command"]["options"]["profile"], "low-latency");
+
+    // The server is closed without other events
+    assert!(matches!(
+        client.wait_event("end-file"),
+        Err(Error::PlayerIpcFailed(_))
+    ));
+
+    std::fs::remove_file(&socket).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn test_ipc_loadfile_error() {
+    let (socket, server) = fake_server(
+        "loadfile-error",
+        "{\"request_id\":2,\"error\":\"invalid parameter\"}\n",
+    );
+
+    let mut client = Client::connect(&socket).unwrap();
+    let result = client.loadfile::<&str>("https://example.com/", LoadFlags::Replace, &[]);
+    server.join().unwrap();
+
+    match result {
+        Err(Error::PlayerIpcFailed(e)) => assert_eq!(e, "invalid parameter"),
+        _ => panic!("unexpected result: {:?}", result),
+    }
+
+    std::fs::remove_file(&socket).unwrap();
+}
@@ -0,0 +1,376 @@
+use crate::config::Config;
+use crate::error::Error;
+use serde_json::{Value, json};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+/// Log file name
+const LOG_FILE: &str = "mpv-handler.log";
+
+/// Default max size of log file (before rotation)
+const DEFAULT_MAX_SIZE: u64 = 1024 * 1024;
+
+/// Number of rotated log files, `mpv-handler.log.1` is the newest one
+const ROTATED_FILES: usize = 3;
+
+/// Query parameter names of secrets, their values are redacted
+const SECRET_PARAMS: &str = "sig|signature|token|access_token|auth|key|api_key|apikey|password|passwd|secret|session|sessionid";
+
+/// Global logger, initialized by `init()`
+static LOGGER: OnceLock<Logger> = OnceLock::new();
+
+/// Log level
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub enum Level {
+    Error,
+    Warn,
+    Info,
+    Debug,
+}
+
+impl Level {
+    /// Returns the name of level
+    pub fn name(&self) -> &'static str {
+        match self {
+            Level::Error => "error",
+            Level::Warn => "warn",
+            Level::Info => "info",
+            Level::Debug => "debug",
+        }
+    }
+}
+
+impl std::str::FromStr for Level {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "error" => Ok(Level::Error),
+            "warn" => Ok(Level::Warn),
+            "info" => Ok(Level::Info),
+            "debug" => Ok(Level::Debug),
+            _ => Err(Error::IncorrectLogLevel(s.to_string())),
+        }
+    }
+}
+
+/// Logger writes JSON lines to log file
+#[derive(Debug)]
+struct Logger {
+    path: PathBuf,
+    level: Level,
+    max_size: u64,
+    lock: Mutex<()>,
+}
+
+/// Initialize global logger by `Config.log_level` and `Config.log_max_size`
+///
+/// Logging is disabled if `log_level` isn't set or is `off`.
+pub fn init(config: &Config) -> Result<(), Error> {
+    let level: Level = match config.log_level.as_deref() {
+        None | Some("off") => return Ok(()),
+        Some(v) => v.parse()?,
+    };
+
+    if let Some(dir) = crate::config::get_state_dir() {
+        let _ = LOGGER.set(Logger {
+            path: dir.join(LOG_FILE),
+            level,
+            max_size: config.log_max_size.unwrap_or(DEFAULT_MAX_SIZE),
+            lock: Mutex::new(()),
+        });
+    }
+
+    Ok(())
+}
+
+/// Write log record of `event` with `fields`, if the logger is initialized
+pub fn log(level: Level, event: &str, fields: Value) {
+    if let Some(logger) = LOGGER.get() {
+        // Logging must not break the handler, only report it
+        if let Err(e) = logger.write(level, event, fields) {
+            eprintln!("Failed to write log ({e})");
+        }
+    }
+}
+
+/// Write error log record
+pub fn error(event: &str, fields: Value) {
+    log(Level::Error, event, fields)
+}
+
+/// Write warning log record
+pub fn warn(event: &str, fields: Value) {
+    log(Level::Warn, event, fields)
+}
+
+/// Write info log record
+pub fn info(event: &str, fields: Value) {
+    log(Level::Info, event, fields)
+}
+
+/// Write debug log record
+pub fn debug(event: &str, fields: Value) {
+    log(Level::Debug, event, fields)
+}
+
+impl Logger {
+    /// Write a JSON line, rotate log files if the size exceeds `max_size`
+    fn write(&self, level: Level, event: &str, fields: Value) -> Result<(), Error> {
+        if level > self.level {
+            return Ok(());
+        }
+
+        let mut record = json!({
+            "time": timestamp(std::time::SystemTime::now()),
+            "level": level.name(),
+            "pid": std::process::id(),
+            "event": event,
+        });
+        if let (Value::Object(record), Value::Object(fields)) = (&mut record, fields) {
+            record.extend(fields);
+        }
+        let line = record.to_string() + "\n";
+
+        let _lock = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+
+        let size = std::fs::metadata(&self.path).map_or(0, |v| v.len());
+        if size > 0 && size + line.len() as u64 > self.max_size {
+            rotate(&self.path)?;
+        }
+
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        file.write_all(line.as_bytes())?;
+
+        Ok(())
+    }
+}
+
+/// Rotate log files: `.log.2` to `.log.3`, `.log.1` to `.log.2`, `.log` to `.log.1`
+fn rotate(path: &Path) -> Result<(), Error> {
+    let rotated = |i: usize| PathBuf::from(format!("{}.{i}", path.display()));
+
+    for i in (1..ROTATED_FILES).rev() {
+        if rotated(i).exists() {
+            std::fs::rename(rotated(i), rotated(i + 1))?;
+        }
+    }
+    std::fs::rename(path, rotated(1))?;
+
+    Ok(())
+}
+
+/// Returns UTC timestamp in RFC 3339 format, e.g. `2024-01-02T03:04:05.678Z`
+pub fn timestamp(time: std::time::SystemTime) -> String {
+    let duration = time
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default();
+    let secs = duration.as_secs();
+    let days = (secs / 86400) as i64;
+    let secs = secs % 86400;
+
+    // Convert days since epoch to civil date (Howard Hinnant's algorithm)
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}.{:03}Z",
+        secs / 3600,
+        secs % 3600 / 60,
+        secs % 60,
+        duration.subsec_millis()
+    )
+}
+
+/// Redact the values of secret query parameters, e.g. `?token=REDACTED`
+pub fn redact(text: &str) -> String {
+    static REGEX: OnceLock<regex::Regex> = OnceLock::new();
+
+    let regex = REGEX.get_or_init(|| {
+        regex::Regex::new(&format!(r"(?i)([?&;](?:{SECRET_PARAMS})=)[^&#\s]*")).unwrap()
+    });
+
+    regex.replace_all(text, "${1}REDACTED").to_string()
+}
+
+/// Redact mpv-handler link, including the secrets in its encoded video URLs
+/// and encoded URL parameters `subfile` and `referrer`
+pub fn redact_arg(arg: &str) -> String {
+    let arg = redact(arg);
+
+    // Find the encoded URLs `SCHEME://PLUGIN/ENCODED_URL/ENCODED_URL/.../?`
+    let start = match arg
+        .find("://")
+        .and_then(|i| arg[i + 3..].find('/').map(|j| i + 3 + j + 1))
+    {
+        Some(v) => v,
+        None => return arg,
+    };
+    let end = arg[start..].find('?').map_or(arg.len(), |i| start + i);
+
+    let urls: Vec<String> = arg[start..end].split('/').map(redact_encoded).collect();
+    let params: Vec<String> = arg[end..]
+        .split('&')
+        .map(|v| match v.split_once('=') {
+            Some((k, v)) if matches!(k.trim_start_matches('?'), "subfile" | "referrer") => {
+                format!("{k}={}", redact_encoded(v))
+            }
+            _ => v.to_string(),
+        })
+        .collect();
+
+    format!("{}{}{}", &arg[..start], urls.join("/"), params.join("&"))
+}
+
+/// Redact the encoded URL of link, it's kept if nothing is redacted
+fn redact_encoded(data: &str) -> String {
+    match crate::protocol::decode_txt(data) {
+        Ok(url) if redact(&url) != url => crate::protocol::encode_txt(&redact(&url)),
+        _ => data.to_string(),
+    }
+}
+
+/// Returns log fields of decoded protocol
+pub fn protocol_fields(proto: &crate::protocol::Protocol) -> Value {
+    let params: serde_json::Map<String, Value> = proto
+        .params()
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), json!(redact(&v))))
+        .collect();
+
+    json!({
+        "scheme": proto.scheme.name(),
+        "plugin": proto.plugin.name(),
+        "url": redact(&proto.url),
+        "params": params,
+    })
+}
+
+/// Returns log fields of command line, the secrets in arguments are redacted
+pub fn argv_fields<S: AsRef<str>>(binary: &str, options: &[String], urls: &[S]) -> Value {
+    let mut argv: Vec<String> = vec![binary.to_string()];
+    argv.extend(options.iter().map(|v| redact(v)));
+    argv.push("--".to_string());
+    argv.extend(urls.iter().map(|v| redact(v.as_ref())));
+
+    json!({ "argv": argv })
+}
+
+#[test]
+fn test_level_parse() {
+    assert_eq!("info".parse::<Level>().unwrap(), Level::Info);
+    assert!(Level::Error < Level::Debug);
+    assert!(matches!(
+        "verbose".parse::<Level>(),
+        Err(Error::IncorrectLogLevel(_))
+    ));
+}
+
+#[test]
+fn test_timestamp() {
+    let time = |secs: u64| std::time::UNIX_EPOCH + std::time::Duration::from_millis(secs);
+
+    assert_eq!(timestamp(time(0)), "1970-01-01T00:00:00.000Z");
+    assert_eq!(timestamp(time(951_782_400_123)), "2000-02-29T00:00:00.123Z");
+    assert_eq!(
+        timestamp(time(1_704_164_645_678)),
+        "2024-01-02T03:04:05.678Z"
+    );
+}
+
+#[test]
+fn test_redact() {
+    assert_eq!(
+        redact("https://example.com/v.m3u8?token=abc&id=1&Sig=xyz"),
+        "https://example.com/v.m3u8?token=REDACTED&id=1&Sig=REDACTED"
+    );
+    assert_eq!(
+        redact("https://www.youtube.com/watch?v=Ggkn2f5e-IU"),
+        "https://www.youtube.com/watch?v=Ggkn2f5e-IU"
+    );
+
+    let url = crate::protocol::encode_txt("https://example.com/v.m3u8?token=abc");
+    let arg = format!("mpv-handler://play/{url}/?quality=720p&sig=abc");
+    let redacted = crate::protocol::encode_txt("https://example.com/v.m3u8?token=REDACTED");
+    assert_eq!(
+        redact_arg(&arg),
+        format!("mpv-handler://play/{redacted}/?quality=720p&sig=REDACTED")
+    );
+
+    // Playlist with multiple encoded URLs
+    let other = crate::protocol::encode_txt("https://example.com/");
+    let arg = format!("mpv-handler://play/{other}/{url}/{url}/?sig=abc");
+    assert_eq!(
+        redact_arg(&arg),
+        format!("mpv-handler://play/{other}/{redacted}/{redacted}/?sig=REDACTED")
+    );
+
+    // Encoded URL parameters
+    let arg = format!("mpv-handler://play/{other}/?subfile={url}&v_title={url}&referrer={url}");
+    assert_eq!(
+        redact_arg(&arg),
+        format!("mpv-handler://play/{other}/?subfile={redacted}&v_title={url}&referrer={redacted}")
+    );
+
+    let arg = "mpv-handler://play/aHR0cHM6Ly9leGFtcGxlLmNvbS8";
+    assert_eq!(redact_arg(arg), arg);
+}
+
+#[test]
+fn test_logger_write() {
+    let dir = crate::testing::temp_path("log");
+    let logger = Logger {
+        path: dir.join(LOG_FILE),
+        level: Level::Info,
+        max_size: 200,
+        lock: Mutex::new(()),
+    };
+
+    logger
+        .write(Level::Info, "exited", json!({ "code": 2 }))
+        .unwrap();
+    logger.write(Level::Debug, "ignored", json!({})).unwrap();
+
+    let data = std::fs::read_to_string(&logger.path).unwrap();
+    let lines: Vec<&str> = data.lines().collect();
+    assert_eq!(lines.len(), 1);
+
+    let record: Value = serde_json::from_str(lines[0]).unwrap();
+    assert_eq!(record["level"], "info");
+    assert_eq!(record["event"], "exited");
+    assert_eq!(record["code"], 2);
+    assert!(record["time"].as_str().unwrap().ends_with('Z'));
+
+    // Rotate by size
+    for _ in 0..10 {
+        logger
+            .write(Level::Error, "error", json!({ "error": "x".repeat(50) }))
+            .unwrap();
+    }
+
+    assert!(std::fs::metadata(&logger.path).unwrap().len() <= 200);
+    for i in 1..=ROTATED_FILES {
+        assert!(dir.join(format!("{LOG_FILE}.{i}")).exists());
+    }
+    assert!(
+        !dir.join(format!("{LOG_FILE}.{}", ROTATED_FILES + 1))
+            .exists()
+    );
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -3,41 +3,181 @@
     windows_subsystem = "windows"
 )]
 
+mod commands;
 mod config;
+mod confirm;
 mod error;
+mod history;
+mod ipc;
+mod log;
+mod native;
+mod notify;
+mod params;
+mod players;
 mod plugins;
 mod protocol;
+mod resolve;
+#[cfg(test)]
+mod testing;
 
 use std::process::ExitCode;
 
 use crate::config::Config;
 use crate::error::Error;
-use crate::plugins::Plugins;
 use crate::protocol::Protocol;
 
 fn main() -> ExitCode {
-    match run() {
+    let args: Vec<String> = std::env::args().collect();
+
+    // Print result as JSON for scripts
+    if args.get(1).is_some_and(|v| v == "--json") {
+        return run_json(&args[2..]);
+    }
+
+    // Subcommands print errors without waiting for a key press,
+    // their exit codes are used by scripts (e.g. `doctor`)
+    if let Some(result) = command(&args) {
+        return match result {
+            Ok(_) => ExitCode::SUCCESS,
+            Err(e) => print_error(e, false),
+        };
+    }
+
+    match open(&args[1..], &mut None) {
         Ok(_) => ExitCode::SUCCESS,
-        Err(e) => print_error(e),
+        Err(e) => print_error(e, true),
     }
 }
 
-/// Run handler
-fn run() -> Result<(), Error> {
-    let args: Vec<String> = std::env::args().collect();
-    let arg: &str = match args.len() {
-        2 => &args[1],
-        1 => return Ok(print_usage()),
-        _ => return Err(Error::TooManyArgs),
+/// Run native messaging host or subcommand, returns `None` if the arguments are links
+fn command(args: &[String]) -> Option<Result<(), Error>> {
+    // Run native messaging host if started by browser
+    if crate::native::is_native(args) {
+        return Some(crate::native::exec());
+    }
+
+    let result = match args.get(1).map(String::as_str) {
+        Some("doctor") => crate::commands::doctor::exec(&args[2..]),
+        Some("encode") => crate::commands::encode::exec(&args[2..]),
+        Some("history") => crate::commands::history::exec(&args[2..]),
+        Some("decode" | "--dry-run") => crate::commands::decode::exec(&args[2..]),
+        #[cfg(unix)]
+        Some("install") => crate::commands::install::exec(&args[2..]),
+        #[cfg(unix)]
+        Some("uninstall") => crate::commands::install::uninstall_exec(&args[2..]),
+        Some("reg-file") => crate::commands::regfile::exec(&args[2..]),
+        Some("serve") => crate::commands::serve::exec(&args[2..]),
+        Some("native-manifest") => crate::commands::manifest::exec(&args[2..]),
+        Some("help" | "--help" | "-h") | None => {
+            print_usage();
+            Ok(())
+        }
+        Some(_) => return None,
     };
 
-    let proto = Protocol::parse(arg)?;
+    Some(result)
+}
+
+/// Run handler with JSON output, the arguments are the same as `run`
+///
+/// Prints `{ "ok": true, "url": ... }`, or the error with its code and kind
+fn run_json(args: &[String]) -> ExitCode {
+    let mut url: Option<String> = None;
+
+    crate::plugins::redirect_stdout();
+
+    let result = match args {
+        [] => Err(Error::MissingArgument("<url>".to_string())),
+        _ => open(args, &mut url),
+    };
+
+    match result {
+        Ok(_) => {
+            println!("{}", serde_json::json!({ "ok": true, "url": url }));
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            crate::log::error("error", serde_json::json!({ "error": e.to_string() }));
+            println!("{}", e.json(url.as_deref()));
+            ExitCode::from(e.code())
+        }
+    }
+}
+
+/// Open mpv-handler links or plain video URLs
+///
+/// All arguments are parsed before running any plugin, `url` is set to the
+/// video URL of the argument being handled. Plain video URLs are played as one
+/// playlist. Each link (or the playlist) is opened by a detached mpv-handler
+/// child process, except the last one, which is opened by this process.
+fn open(args: &[String], url: &mut Option<String>) -> Result<(), Error> {
     let config = Config::load()?;
+    let mut groups: Vec<(Vec<&String>, Protocol)> = Vec::new();
+    let mut playlist: Option<usize> = None;
 
-    // Call plugin by scheme
-    match proto.plugin {
-        Plugins::Play => crate::plugins::play::exec(&proto, &config),
+    crate::log::init(&config)?;
+
+    for arg in args {
+        crate::log::info(
+            "invocation",
+            serde_json::json!({ "arg": crate::log::redact_arg(arg) }),
+        );
+
+        *url = None;
+        let proto = Protocol::parse_arg(arg, &config)?;
+        *url = Some(proto.url.clone());
+
+        let link = crate::protocol::is_link(arg);
+
+        match playlist {
+            Some(i) if !link => {
+                groups[i].0.push(arg);
+                groups[i].1.playlist.push(proto.url);
+            }
+            _ => {
+                if !link {
+                    playlist = Some(groups.len());
+                }
+                groups.push((vec![arg], proto));
+            }
+        }
+    }
+
+    for (_, proto) in &mut groups {
+        *url = Some(proto.url.clone());
+
+        // Apply default parameters of matched site rule
+        if let Some(site) = config.site(&proto.url) {
+            crate::log::debug(
+                "site",
+                serde_json::json!({ "host": site.host, "regex": site.regex }),
+            );
+            proto.apply_site(site)?;
+        }
+    }
+
+    let (_, proto) = match groups.pop() {
+        Some(v) => v,
+        None => return Ok(()),
+    };
+
+    for (args, _) in &groups {
+        crate::log::info(
+            "detach",
+            serde_json::json!({
+                "args": args.iter().map(|v| crate::log::redact_arg(v)).collect::<Vec<String>>(),
+            }),
+        );
+
+        let mut command = std::process::Command::new(std::env::current_exe()?);
+        crate::native::spawn(command.args(args))?;
     }
+
+    *url = Some(proto.url.clone());
+    crate::log::info("protocol", crate::log::protocol_fields(&proto));
+
+    // Call plugin by scheme
+    crate::plugins::exec(&proto, &config)
 }
 
 /// Print usage
@@ -45,16 +185,59 @@ fn print_usage() {
     let version: &str = option_env!("MPV_HANDLER_VERSION").unwrap_or(env!("CARGO_PKG_VERSION"));
 
     println!("mpv-handler {}\n", version);
-    println!("Usage:\n  {}\n", "mpv-handler <url>",);
+    println!("Usage:\n  mpv-handler <url>...\n  mpv-handler --json <url>...");
+    println!("  mpv-handler --dry-run [--json] <url>");
+    println!("  mpv-handler <command> [options]\n");
+    println!("Commands:");
+    println!("  doctor                    Diagnose the installation");
+    println!("  encode [options] <url>... Print mpv-handler link of the video URLs");
+    println!("      --plugin <name>       Plugin name (play, queue, download)");
+    println!("      --debug               Use scheme mpv-handler-debug");
+    println!("      --cookies <file>      Parameter cookies");
+    println!("      --profile <name>      Parameter profile");
+    println!("      --quality <quality>   Parameter quality");
+    println!("      --v-codec <codec>     Parameter v_codec");
+    println!("      --title <title>       Parameter v_title");
+    println!("      --subfile <url>       Parameter subfile");
+    println!("      --startat <time>      Parameter startat");
+    println!("      --referrer <url>      Parameter referrer");
+    println!("      --playlist-start <n>  Parameter playlist_start");
+    println!("      --shuffle             Parameter shuffle");
+    println!("      --expires <seconds>   Parameter exp, seconds from now");
+    println!("      --sign                Sign link by sign_secret of config");
+    println!("  decode [options] <link>   Print what the link would do without running player");
+    println!("      --json                Print as JSON");
+    println!("  history [list] [options]  List playback history");
+    println!("      --limit <n>           Number of latest entries (default: 20)");
+    println!("  history search <text>     Search playback history by URL, title or site");
+    println!("  history replay <id>       Play the history entry again");
+    println!("  history clear             Remove all playback history");
+    #[cfg(unix)]
+    println!("  install                   Register mpv-handler as scheme handler");
+    #[cfg(unix)]
+    println!("  uninstall                 Unregister mpv-handler as scheme handler");
+    println!("  reg-file [options]        Write Windows registry file of scheme handlers");
+    println!("      --uninstall           Write registry file to unregister");
+    println!("      --output <file>       Output file (\"-\" for stdout)");
+    println!("  serve [options]           Run HTTP bridge on loopback address");
+    println!("      --port <port>         Listening port (default: 3210)");
+    println!("  native-manifest [options] Install native messaging host manifests");
+    println!("      --firefox <id>        Allow Firefox extension ID");
+    println!("      --chromium <id>       Allow Chromium extension ID");
 }
 
-/// Print error
-fn print_error(e: Error) -> ExitCode {
-    eprint!("{e}");
-    std::io::Read::read(&mut std::io::stdin(), &mut []).unwrap();
+/// Print error, and report it to user if `report` is set
+fn print_error(e: Error, report: bool) -> ExitCode {
+    crate::log::error("error", serde_json::json!({ "error": e.to_string() }));
 
-    match e {
-        Error::PlayerExited(code) => ExitCode::from(code),
-        _ => ExitCode::FAILURE,
+    eprintln!("{e}");
+
+    // Wait for a key press in terminal, or notify user
+    if report {
+        let arg = std::env::args().nth(1);
+        let config = Config::load().unwrap_or_default();
+        crate::notify::report(&e.to_string(), arg.as_deref(), &config);
     }
+
+    ExitCode::from(e.code())
 }
@@ -0,0 +1,315 @@
+use crate::config::Config;
+use crate::error::Error;
+use crate::protocol::Protocol;
+use serde_json::{Value, json};
+use std::io::{Read, Write};
+
+/// Name of native messaging host
+pub const HOST_NAME: &str = "mpv_handler";
+
+/// Max length of native message
+const MAX_LENGTH: u32 = 1024 * 1024;
+
+/// Check the arguments are given by browser to start native messaging host
+///
+/// - Chromium: `chrome-extension://EXTENSION_ID/`
+/// - Firefox: `/path/of/mpv_handler.json EXTENSION_ID`
+pub fn is_native(args: &[String]) -> bool {
+    match args.get(1) {
+        Some(v) if v.starts_with("chrome-extension://") => true,
+        Some(v) => args.len() == 3 && v.ends_with(&format!("{HOST_NAME}.json")),
+        None => false,
+    }
+}
+
+/// Run native messaging host
+///
+/// Read messages from stdin, run the link of each message,
+/// and write replies to stdout. Exit when stdin is closed.
+pub fn exec() -> Result<(), Error> {
+    let mut stdin = std::io::stdin().lock();
+    let mut stdout = std::io::stdout().lock();
+
+    while let Some(message) = read_message(&mut stdin)? {
+        write_message(&mut stdout, &reply(handle(&message)))?;
+    }
+
+    Ok(())
+}
+
+/// Returns reply of message, the error has the same fields as `--json` output
+fn reply(result: Result<String, Error>) -> Value {
+    match result {
+        Ok(url) => json!({ "ok": true, "url": url }),
+        Err(e) => e.json(None),
+    }
+}
+
+/// Check the message and run its link
+fn handle(message: &Value) -> Result<String, Error> {
+    run(&link(message)?)
+}
+
+/// Check the link and run it by detached mpv-handler child process, returns the video URL
+///
+/// Replies as soon as the child process is started, it doesn't wait for player.
+/// So only the errors of link and starting child process are returned, the errors
+/// of child process are reported by itself (see `crate::notify::report`).
+pub fn run(link: &str) -> Result<String, Error> {
+    let config = Config::load()?;
+    let url = Protocol::parse(link, &config)?.url;
+
+    let mut command = std::process::Command::new(std::env::current_exe()?);
+    command.arg(link);
+    spawn(&mut command)?;
+
+    Ok(url)
+}
+
+/// Start command without stdio, and wait for it in background
+///
+/// The child process has no stdout, so the outputs of plugins and player
+/// can't break the messages (or responses of `serve`).
+pub fn spawn(command: &mut std::process::Command) -> Result<(), Error> {
+    let mut child = command
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+        .map_err(Error::PlayerRunFailed)?;
+
+    // Reap the child process after it exits
+    std::thread::spawn(move || child.wait());
+
+    Ok(())
+}
+
+/// Convert native message to mpv-handler link
+///
+/// The message is a link `{ "link": "mpv-handler://..." }`, or the fields of
+/// `Protocol` with decoded values:
+///
+/// ```
+/// { "plugin": "play", "url": "https://...", "v_title": "...", "debug": false, ... }
+/// ```
+///
+/// The fields are checked and encoded by `Protocol`, so a value can't add other parameters.
+fn link(message: &Value) -> Result<String, Error> {
+    let field = |k: &str| -> Result<Option<&str>, Error> {
+        match &message[k] {
+            Value::Null => Ok(None),
+            Value::String(v) => Ok(Some(v)),
+            _ => Err(Error::IncorrectNativeMessage(format!(
+                "\"{k}\" is not a string"
+            ))),
+        }
+    };
+
+    if let Some(v) = field("link")? {
+        return Ok(v.to_string());
+    }
+
+    let mut proto = match field("url")? {
+        Some(v) => Protocol::from_url(v.to_string()),
+        None => return Err(Error::IncorrectNativeMessage("missing \"url\"".to_string())),
+    };
+
+    if message["debug"].as_bool() == Some(true) {
+        proto.scheme = crate::protocol::Schemes::MpvHandlerDebug;
+    }
+    if let Some(v) = field("plugin")? {
+        proto.plugin = crate::plugins::Plugins::from_name(v).ok_or(
+            Error::IncorrectNativeMessage(format!("incorrect \"plugin\" \"{v}\"")),
+        )?;
+    }
+    if let Some(v) = field("cookies")? {
+        // The cookies file name is the only parameter not checked by its type
+        if v.contains(['&', '=', '?', '#', '/', '\\']) {
+            return Err(Error::IncorrectCookies(v.to_string()));
+        }
+        proto.cookies = Some(v);
+    }
+
+    proto.profile = field("profile")?.map(crate::params::profile).transpose()?;
+    proto.quality = field("quality")?.map(str::parse).transpose()?;
+    proto.v_codec = field("v_codec")?.map(str::parse).transpose()?;
+    proto.startat = field("startat")?.map(str::parse).transpose()?;
+    proto.v_title = field("v_title")?.map(str::to_string);
+    proto.subfile = field("subfile")?.map(str::to_string);
+    proto.referrer = field("referrer")?.map(str::to_string);
+
+    Ok(proto.encode())
+}
+
+/// Read a message, which has 4 bytes length (native byte order) and JSON data
+///
+/// Returns `None` if stdin is closed
+fn read_message<R: Read>(reader: &mut R) -> Result<Option<Value>, Error> {
+    let mut length = [0u8; 4];
+
+    match reader.read_exact(&mut length) {
+        Ok(_) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e.into()),
+    }
+
+    let length = u32::from_ne_bytes(length);
+
+    if length > MAX_LENGTH {
+        return Err(Error::IncorrectNativeMessage(format!(
+            "too long ({length} bytes)"
+        )));
+    }
+
+    let mut data = vec![0u8; length as usize];
+    reader.read_exact(&mut data)?;
+
+    Ok(Some(serde_json::from_slice(&data)?))
+}
+
+/// Write a message, which has 4 bytes length (native byte order) and JSON data
+fn write_message<W: Write>(writer: &mut W, message: &Value) -> Result<(), Error> {
+    let data = message.to_string();
+
+    writer.write_all(&(data.len() as u32).to_ne_bytes())?;
+    writer.write_all(data.as_bytes())?;
+    writer.flush()?;
+
+    Ok(())
+}
+
+#[test]
+fn test_is_native() {
+    let args = crate::testing::strings;
+
+    assert!(is_native(&args(&[
+        "mpv-handler",
+        "chrome-extension://abcdefg/"
+    ])));
+    assert!(is_native(&args(&[
+        "mpv-handler",
+        "/home/user/.mozilla/native-messaging-hosts/mpv_handler.json",
+        "mpv-handler@example.com",
+    ])));
+    assert!(!is_native(&args(&[
+        "mpv-handler",
+        "mpv-handler://play/aHR0cHM6Ly9leGFtcGxlLmNvbS8"
+    ])));
+    assert!(!is_native(&args(&["mpv-handler"])));
+}
+
+#[test]
+fn test_native_messages() {
+    let mut data: Vec<u8> = Vec::new();
+    write_message(&mut data, &json!({ "url": "https://example.com/" })).unwrap();
+    write_message(&mut data, &json!({ "ok": true })).unwrap();
+
+    assert_eq!(&data[..4], &30u32.to_ne_bytes());
+
+    let mut reader = data.as_slice();
+    let message = read_message(&mut reader).unwrap().unwrap();
+    assert_eq!(message["url"], "https://example.com/");
+    let message = read_message(&mut reader).unwrap().unwrap();
+    assert_eq!(message["ok"], true);
+    assert!(read_message(&mut reader).unwrap().is_none());
+
+    // Too long message
+    let data = (MAX_LENGTH + 1).to_ne_bytes();
+    assert!(matches!(
+        read_message(&mut data.as_slice()),
+        Err(Error::IncorrectNativeMessage(_))
+    ));
+}
+
+#[test]
+fn test_native_link() {
+    let config = Config::default();
+
+    let l = link(&json!({
+        "plugin": "queue",
+        "url": "https://www.youtube.com/watch?v=Ggkn2f5e-IU",
+        "quality": "720p",
+        "v_title": "Title",
+        "debug": true,
+    }))
+    .unwrap();
+    let proto = Protocol::parse(&l, &config).unwrap();
+
+    assert_eq!(proto.scheme, crate::protocol::Schemes::MpvHandlerDebug);
+    assert_eq!(proto.plugin, crate::plugins::Plugins::Queue);
+    assert_eq!(proto.url, "https://www.youtube.com/watch?v=Ggkn2f5e-IU");
+    assert_eq!(proto.quality, Some(crate::params::Quality::P720));
+    assert_eq!(proto.v_title, Some("Title".to_string()));
+
+    let l = link(&json!({ "url": "https://example.com/" })).unwrap();
+    assert_eq!(l, "mpv-handler://play/aHR0cHM6Ly9leGFtcGxlLmNvbS8/");
+
+    // Values can't add other parameters
+    let l = link(&json!({
+        "url": "https://example.com/",
+        "v_title": "a&startat=50%",
+        "referrer": "https://example.com/?a=b&c=d",
+    }))
+    .unwrap();
+    let proto = Protocol::parse(&l, &config).unwrap();
+    assert_eq!(proto.v_title, Some("a&startat=50%".to_string()));
+    assert_eq!(
+        proto.referrer,
+        Some("https://example.com/?a=b&c=d".to_string())
+    );
+    assert_eq!(proto.startat, None);
+
+    assert!(matches!(
+        link(&json!({ "url": "https://example.com/", "cookies": "a.txt&startat=50%" })),
+        Err(Error::IncorrectCookies(_))
+    ));
+    assert!(matches!(
+        link(&json!({ "url": "https://example.com/", "quality": "720p&startat=50%" })),
+        Err(Error::IncorrectQuality(_))
+    ));
+    assert!(matches!(
+        link(&json!({ "url": "https://example.com/", "plugin": "play/x" })),
+        Err(Error::IncorrectNativeMessage(_))
+    ));
+
+    let l = link(&json!({ "link": "mpv-handler://play/aHR0cHM6Ly9leGFtcGxlLmNvbS8" })).unwrap();
+    assert_eq!(l, "mpv-handler://play/aHR0cHM6Ly9leGFtcGxlLmNvbS8");
+
+    assert!(matches!(
+        link(&json!({ "plugin": "play" })),
+        Err(Error::IncorrectNativeMessage(_))
+    ));
+    assert!(matches!(
+        link(&json!({ "url": 1 })),
+        Err(Error::IncorrectNativeMessage(_))
+    ));
+}
+
+#[test]
+fn test_native_reply() {
+    assert_eq!(
+        reply(Ok("https://example.com/".to_string())),
+        json!({ "ok": true, "url": "https://example.com/" })
+    );
+
+    let r = reply(link(
+        &json!({ "url": "https://example.com/", "quality": "1k" }),
+    ));
+    assert_eq!(r["ok"], false);
+    assert_eq!(r["code"], 26);
+    assert_eq!(r["kind"], "incorrect_quality");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_spawn() {
+    // Returns before the command exits
+    let time = std::time::Instant::now();
+    spawn(std::process::Command::new("sleep").arg("5")).unwrap();
+    assert!(time.elapsed() < std::time::Duration::from_secs(2));
+
+    assert!(matches!(
+        spawn(&mut std::process::Command::new("/nonexistent/mpv-handler")),
+        Err(Error::PlayerRunFailed(_))
+    ));
+}
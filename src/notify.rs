@@ -0,0 +1,104 @@
+use crate::config::Config;
+use std::io::IsTerminal;
+
+/// Report error message to user
+///
+/// Only used for the errors of links. Wait for a key press for debug schemes
+/// (`mpv-handler-debug` and the deprecated `mpv-debug`) or a real terminal,
+/// otherwise nobody can press a key (e.g. launched by browser), send a desktop
+/// notification by `Config.notify_command` instead.
+pub fn report(message: &str, arg: Option<&str>, config: &Config) {
+    if is_interactive(arg) {
+        let _ = std::io::Read::read(&mut std::io::stdin(), &mut []);
+        return;
+    }
+
+    let command = match &config.notify_command {
+        Some(v) => v.clone(),
+        None => default_command(),
+    };
+
+    // Notification must not break the handler, only report it
+    if let Err(e) = notify(&command, message) {
+        crate::log::warn("notify", serde_json::json!({ "error": e.to_string() }));
+    }
+}
+
+/// Check user can answer in terminal
+fn is_interactive(arg: Option<&str>) -> bool {
+    arg.is_some_and(|v| v.starts_with("mpv-handler-debug://") || v.starts_with("mpv-debug://"))
+        || std::io::stdin().is_terminal()
+}
+
+/// The default value of `Config.notify_command`
+///
+/// There is no `notify-send` on Windows, notification is disabled by default.
+fn default_command() -> Vec<String> {
+    #[cfg(unix)]
+    return ["notify-send", "--app-name=mpv-handler", "mpv-handler"]
+        .iter()
+        .map(|v| v.to_string())
+        .collect();
+    #[cfg(windows)]
+    return Vec::new();
+}
+
+/// Send notification by external command, the message is appended as the last argument
+///
+/// An empty command disables notification.
+fn notify(command: &[String], message: &str) -> Result<(), std::io::Error> {
+    let (program, args) = match command.split_first() {
+        Some(v) => v,
+        None => return Ok(()),
+    };
+
+    std::process::Command::new(program)
+        .args(args)
+        .arg(message)
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()?;
+
+    Ok(())
+}
+
+#[test]
+fn test_is_interactive() {
+    assert!(is_interactive(Some(
+        "mpv-handler-debug://play/aHR0cHM6Ly9leGFtcGxlLmNvbS8"
+    )));
+    assert!(is_interactive(Some(
+        "mpv-debug://play/aHR0cHM6Ly9leGFtcGxlLmNvbS8"
+    )));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_notify() {
+    let dir = crate::testing::temp_path("notify");
+    let output = dir.join("output");
+    std::fs::create_dir_all(&dir).unwrap();
+
+    // Stub command records its arguments
+    let command: Vec<String> = vec![
+        "sh".to_string(),
+        "-c".to_string(),
+        format!("printf '%s|' \"$@\" > '{}'", output.display()),
+        "sh".to_string(),
+        "mpv-handler".to_string(),
+    ];
+    notify(&command, "Player exited by error").unwrap();
+    assert_eq!(
+        std::fs::read_to_string(&output).unwrap(),
+        "mpv-handler|Player exited by error|"
+    );
+
+    // Disabled
+    notify(&[], "message").unwrap();
+
+    let missing = vec!["/nonexistent/mpv-handler-notify".to_string()];
+    assert!(notify(&missing, "message").is_err());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,244 @@
+use crate::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// Start position of video
+///
+/// - `233`, `233.5`: seconds
+/// - `01:02:03`, `02:03.5`: `[[hh:]mm:]ss`
+/// - `50%`: percentage of video duration
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StartAt {
+    Seconds(f64),
+    Percent(f64),
+}
+
+impl FromStr for StartAt {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || Error::IncorrectStartAt(s.to_string());
+
+        if let Some(v) = s.strip_suffix('%') {
+            return match number(v) {
+                Some(v) if v <= 100.0 => Ok(StartAt::Percent(v)),
+                _ => Err(err()),
+            };
+        }
+
+        let parts: Vec<&str> = s.split(':').collect();
+
+        if parts.len() > 3 {
+            return Err(err());
+        }
+
+        let mut seconds: f64 = 0.0;
+
+        for (i, part) in parts.iter().enumerate() {
+            let last = i == parts.len() - 1;
+
+            // Hours and minutes must be integers
+            if !last && !part.bytes().all(|c| c.is_ascii_digit()) {
+                return Err(err());
+            }
+
+            let v = number(part).ok_or_else(err)?;
+
+            // Minutes and seconds must be less than 60 after another field
+            if i > 0 && v >= 60.0 {
+                return Err(err());
+            }
+
+            seconds = seconds * 60.0 + v;
+        }
+
+        Ok(StartAt::Seconds(seconds))
+    }
+}
+
+impl fmt::Display for StartAt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StartAt::Seconds(v) => write!(f, "{v}"),
+            StartAt::Percent(v) => write!(f, "{v}%"),
+        }
+    }
+}
+
+/// Video resolution
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Quality {
+    P2160,
+    P1440,
+    P1080,
+    P720,
+    P480,
+    P360,
+}
+
+impl Quality {
+    /// Returns the height of resolution
+    pub fn height(&self) -> u32 {
+        match self {
+            Quality::P2160 => 2160,
+            Quality::P1440 => 1440,
+            Quality::P1080 => 1080,
+            Quality::P720 => 720,
+            Quality::P480 => 480,
+            Quality::P360 => 360,
+        }
+    }
+}
+
+impl FromStr for Quality {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "2160p" => Ok(Quality::P2160),
+            "1440p" => Ok(Quality::P1440),
+            "1080p" => Ok(Quality::P1080),
+            "720p" => Ok(Quality::P720),
+            "480p" => Ok(Quality::P480),
+            "360p" => Ok(Quality::P360),
+            _ => Err(Error::IncorrectQuality(s.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for Quality {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}p", self.height())
+    }
+}
+
+/// Video codec
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VideoCodec {
+    Av01,
+    Vp9,
+    H265,
+    H264,
+}
+
+impl FromStr for VideoCodec {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "av01" => Ok(VideoCodec::Av01),
+            "vp9" => Ok(VideoCodec::Vp9),
+            "h265" => Ok(VideoCodec::H265),
+            "h264" => Ok(VideoCodec::H264),
+            _ => Err(Error::IncorrectVideoCodec(s.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for VideoCodec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            VideoCodec::Av01 => write!(f, "av01"),
+            VideoCodec::Vp9 => write!(f, "vp9"),
+            VideoCodec::H265 => write!(f, "h265"),
+            VideoCodec::H264 => write!(f, "h264"),
+        }
+    }
+}
+
+/// Check mpv profile name and returns it
+///
+/// Allowed characters: `A-Z`, `a-z`, `0-9`, `-`, `_`, `.`,
+/// and the first character must be a letter or digit
+pub fn profile(s: &str) -> Result<&str, Error> {
+    let allowed = |c: char| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.';
+
+    if s.len() > 64
+        || !s.starts_with(|c: char| c.is_ascii_alphanumeric())
+        || !s.chars().all(allowed)
+    {
+        return Err(Error::IncorrectProfile(s.to_string()));
+    }
+
+    Ok(s)
+}
+
+/// Parse non-negative decimal number, without sign and exponent
+fn number(s: &str) -> Option<f64> {
+    let mut digits = false;
+    let mut dot = false;
+
+    for c in s.chars() {
+        match c {
+            '0'..='9' => digits = true,
+            '.' if !dot => dot = true,
+            _ => return None,
+        }
+    }
+
+    if !digits {
+        return None;
+    }
+
+    s.parse().ok()
+}
+
+#[test]
+fn test_startat_parse() {
+    assert_eq!("233".parse::<StartAt>().unwrap(), StartAt::Seconds(233.0));
+    assert_eq!("233.5".parse::<StartAt>().unwrap(), StartAt::Seconds(233.5));
+    assert_eq!("02:03".parse::<StartAt>().unwrap(), StartAt::Seconds(123.0));
+    assert_eq!(
+        "01:02:03.5".parse::<StartAt>().unwrap(),
+        StartAt::Seconds(3723.5)
+    );
+    assert_eq!("50%".parse::<StartAt>().unwrap(), StartAt::Percent(50.0));
+    assert_eq!("12.5%".parse::<StartAt>().unwrap(), StartAt::Percent(12.5));
+
+    for s in [
+        "", "-1", "1e3", "abc", "1:60", "1.5:00", "1:2:3:4", "101%", "%", "1:", "--pause",
+    ] {
+        assert!(
+            matches!(s.parse::<StartAt>(), Err(Error::IncorrectStartAt(_))),
+            "{s:?} should be rejected"
+        );
+    }
+
+    assert_eq!(StartAt::Seconds(233.0).to_string(), "233");
+    assert_eq!(StartAt::Seconds(3723.5).to_string(), "3723.5");
+    assert_eq!(StartAt::Percent(50.0).to_string(), "50%");
+}
+
+#[test]
+fn test_quality_parse() {
+    assert_eq!("1080p".parse::<Quality>().unwrap(), Quality::P1080);
+    assert_eq!(Quality::P720.to_string(), "720p");
+    assert!(matches!(
+        "1080".parse::<Quality>(),
+        Err(Error::IncorrectQuality(_))
+    ));
+    assert!(matches!(
+        "999p".parse::<Quality>(),
+        Err(Error::IncorrectQuality(_))
+    ));
+}
+
+#[test]
+fn test_video_codec_parse() {
+    assert_eq!("av01".parse::<VideoCodec>().unwrap(), VideoCodec::Av01);
+    assert_eq!(VideoCodec::H265.to_string(), "h265");
+    assert!(matches!(
+        "av1".parse::<VideoCodec>(),
+        Err(Error::IncorrectVideoCodec(_))
+    ));
+}
+
+#[test]
+fn test_profile_check() {
+    assert_eq!(profile("low-latency").unwrap(), "low-latency");
+    assert_eq!(profile("my_profile.1").unwrap(), "my_profile.1");
+
+    for s in ["", "a b", "a,b", "a=b", "--pause", ".hidden", "a\nb"] {
+        assert!(matches!(profile(s), Err(Error::IncorrectProfile(_))));
+    }
+}
@@ -0,0 +1,82 @@
+use crate::config::Config;
+use crate::error::Error;
+use crate::players::Player;
+use crate::protocol::Protocol;
+use crate::resolve::Video;
+
+/// Prefix of mpv options passed through Celluloid
+const PREFIX_MPV: &str = "--mpv-";
+
+/// Celluloid, the GTK frontend of mpv
+///
+/// Celluloid passes the options prefixed with `--mpv-` to mpv,
+/// so the options are the same as mpv player.
+pub struct Celluloid;
+
+impl Player for Celluloid {
+    fn name(&self) -> &'static str {
+        "celluloid"
+    }
+
+    fn binary(&self, config: &Config) -> Result<String, Error> {
+        match &config.celluloid {
+            Some(v) => Ok(v.clone()),
+            None => crate::config::default_celluloid(),
+        }
+    }
+
+    fn ytdl(&self) -> bool {
+        true
+    }
+
+    fn options(&self, proto: &Protocol, config: &Config) -> Result<Vec<String>, Error> {
+        let options = crate::players::mpv::options(proto, config)?;
+        Ok(options.iter().map(|v| mpv_option(v)).collect())
+    }
+
+    fn playlist_options(&self, proto: &Protocol) -> Vec<String> {
+        let options = crate::players::mpv::Mpv.playlist_options(proto);
+        options.iter().map(|v| mpv_option(v)).collect()
+    }
+
+    fn video_options(&self, video: &Video) -> Result<Vec<String>, Error> {
+        let options = crate::players::mpv::Mpv.video_options(video)?;
+        Ok(options.iter().map(|v| mpv_option(v)).collect())
+    }
+}
+
+/// Returns mpv option of Celluloid, e.g. `--title=` to `--mpv-title=`
+fn mpv_option(option: &str) -> String {
+    match option.strip_prefix("--") {
+        Some(v) => format!("{PREFIX_MPV}{v}"),
+        None => option.to_string(),
+    }
+}
+
+#[test]
+fn test_celluloid_options() {
+    let config = Config::default();
+    let proto = Protocol::parse(
+        "mpv-handler://play/aHR0cHM6Ly9leGFtcGxlLmNvbS8/?v_title=VGl0bGU&profile=low-latency",
+        &config,
+    )
+    .unwrap();
+
+    assert_eq!(
+        Celluloid.options(&proto, &config).unwrap(),
+        ["--mpv-profile=low-latency", "--mpv-title=Title"]
+    );
+
+    let video = crate::resolve::Video {
+        url: "https://example.com/v.mp4".to_string(),
+        audio_url: Some("https://example.com/a.m4a".to_string()),
+        ..Default::default()
+    };
+    assert_eq!(
+        Celluloid.video_options(&video).unwrap(),
+        [
+            "--mpv-ytdl=no",
+            "--mpv-audio-file=https://example.com/a.m4a"
+        ]
+    );
+}
@@ -0,0 +1,85 @@
+use crate::config::Config;
+use crate::error::Error;
+use crate::protocol::Protocol;
+use crate::resolve::Video;
+
+pub mod celluloid;
+pub mod mpv;
+pub mod vlc;
+
+/// Player backend, translates `Protocol` into the command line of player
+pub trait Player {
+    /// Returns the name of player
+    fn name(&self) -> &'static str;
+
+    /// Returns player binary path
+    fn binary(&self, config: &Config) -> Result<String, Error>;
+
+    /// Check the player opens the URL of video site by yt-dlp itself
+    ///
+    /// Otherwise, the video URL is resolved by yt-dlp before playing.
+    fn ytdl(&self) -> bool;
+
+    /// Returns player options of given protocol
+    fn options(&self, proto: &Protocol, config: &Config) -> Result<Vec<String>, Error>;
+
+    /// Returns player options of playlist, e.g. start index and shuffle
+    ///
+    /// They are only applied when starting a new player.
+    fn playlist_options(&self, proto: &Protocol) -> Vec<String>;
+
+    /// Returns player options of the video resolved by yt-dlp
+    ///
+    /// e.g. separate audio URL, HTTP headers, title and chapters
+    fn video_options(&self, video: &Video) -> Result<Vec<String>, Error>;
+
+    /// Returns options of a resolved playlist item, they are placed after its URL
+    ///
+    /// Only used by the player without yt-dlp, which gets resolved playlist.
+    fn item_options(&self, _video: &Video) -> Result<Vec<String>, Error> {
+        Ok(Vec::new())
+    }
+}
+
+/// Returns player by name
+pub fn from_name(name: &str) -> Option<&'static dyn Player> {
+    match name {
+        "mpv" => Some(&mpv::Mpv),
+        "vlc" => Some(&vlc::Vlc),
+        "celluloid" => Some(&celluloid::Celluloid),
+        _ => None,
+    }
+}
+
+/// Returns player of the given URL, by matched site rule or `Config.player`
+pub fn player(config: &Config, url: &str) -> Result<&'static dyn Player, Error> {
+    let name = config.player(url);
+    from_name(name).ok_or(Error::IncorrectPlayer(name.to_string()))
+}
+
+#[test]
+fn test_player() {
+    let config: Config = toml::from_str(
+        r#"
+            player = "vlc"
+
+            [[site]]
+            host = "*.twitch.tv"
+            player = "celluloid"
+        "#,
+    )
+    .unwrap();
+
+    let p = player(&config, "https://www.youtube.com/watch?v=Ggkn2f5e-IU").unwrap();
+    assert_eq!(p.name(), "vlc");
+    assert!(!p.ytdl());
+
+    let p = player(&config, "https://www.twitch.tv/example").unwrap();
+    assert_eq!(p.name(), "celluloid");
+
+    let p = player(&Config::default(), "https://example.com/").unwrap();
+    assert_eq!(p.name(), "mpv");
+    assert!(p.ytdl());
+
+    assert!(from_name("mplayer").is_none());
+}
@@ -0,0 +1,283 @@
+use crate::config::Config;
+use crate::error::Error;
+use crate::params::{Quality, StartAt, VideoCodec};
+use crate::players::Player;
+use crate::protocol::Protocol;
+use crate::resolve::Video;
+
+const PREFIX_COOKIES: &str = "--ytdl-raw-options-append=cookies=";
+const PREFIX_PROFILE: &str = "--profile=";
+const PREFIX_FORMATS: &str = "--ytdl-raw-options-append=format-sort=";
+const PREFIX_V_TITLE: &str = "--title=";
+const PREFIX_SUBFILE: &str = "--sub-file=";
+const PREFIX_STARTAT: &str = "--start=";
+const PREFIX_REFERRER: &str = "--referrer=";
+const PREFIX_YT_PATH: &str = "--script-opts=ytdl_hook-ytdl_path=";
+const PREFIX_IPC_SERVER: &str = "--input-ipc-server=";
+const PREFIX_AUDIO_FILE: &str = "--audio-file=";
+const PREFIX_HTTP_HEADER: &str = "--http-header-fields-append=";
+const PREFIX_MEDIA_TITLE: &str = "--force-media-title=";
+const PREFIX_CHAPTERS: &str = "--chapters-file=";
+const NO_YTDL: &str = "--ytdl=no";
+const PREFIX_PLAYLIST_START: &str = "--playlist-start=";
+const SHUFFLE: &str = "--shuffle";
+
+/// mpv player, the default player
+pub struct Mpv;
+
+impl Player for Mpv {
+    fn name(&self) -> &'static str {
+        "mpv"
+    }
+
+    fn binary(&self, config: &Config) -> Result<String, Error> {
+        match &config.mpv {
+            Some(v) => Ok(v.clone()),
+            None => crate::config::default_mpv(),
+        }
+    }
+
+    fn ytdl(&self) -> bool {
+        true
+    }
+
+    fn options(&self, proto: &Protocol, config: &Config) -> Result<Vec<String>, Error> {
+        options(proto, config)
+    }
+
+    fn playlist_options(&self, proto: &Protocol) -> Vec<String> {
+        let mut options: Vec<String> = Vec::new();
+
+        if let Some(v) = proto.playlist_start {
+            options.push(format!("{PREFIX_PLAYLIST_START}{v}"));
+        }
+
+        if proto.shuffle {
+            options.push(SHUFFLE.to_string());
+        }
+
+        options
+    }
+
+    fn video_options(&self, video: &Video) -> Result<Vec<String>, Error> {
+        // The video is resolved already, don't run yt-dlp again by ytdl_hook
+        let mut options: Vec<String> = vec![NO_YTDL.to_string()];
+
+        if let Some(v) = &video.audio_url {
+            options.push(format!("{PREFIX_AUDIO_FILE}{v}"));
+        }
+
+        for (k, v) in &video.headers {
+            options.push(format!("{PREFIX_HTTP_HEADER}{k}: {v}"));
+        }
+
+        if let Some(v) = &video.title {
+            options.push(format!("{PREFIX_MEDIA_TITLE}{v}"));
+        }
+
+        if let Some(v) = crate::resolve::write_chapters(video)? {
+            options.push(format!("{PREFIX_CHAPTERS}{}", v.display()));
+        }
+
+        Ok(options)
+    }
+}
+
+/// Returns mpv options of given protocol
+pub fn options(proto: &Protocol, config: &Config) -> Result<Vec<String>, Error> {
+    let mut options: Vec<String> = Vec::new();
+
+    // Append cookies option
+    if let Some(v) = proto.cookies
+        && let Some(v) = cookies(v)?
+    {
+        options.push(v);
+    }
+
+    // Append profile option
+    if let Some(v) = proto.profile {
+        options.push(profile(v));
+    }
+
+    // Append formats option
+    if (proto.quality.is_some() || proto.v_codec.is_some())
+        && let Some(v) = formats(proto.quality, proto.v_codec)
+    {
+        options.push(v);
+    }
+
+    // Append v_title option
+    if let Some(v) = &proto.v_title {
+        options.push(v_title(v));
+    }
+
+    // Append subfile option
+    if let Some(v) = &proto.subfile {
+        options.push(subfile(v));
+    }
+
+    // Append startat option
+    if let Some(v) = proto.startat {
+        options.push(startat(v));
+    }
+
+    // Append referrer option
+    if let Some(v) = &proto.referrer {
+        options.push(referrer(v));
+    }
+
+    // Set custom ytdl execute file path
+    if let Some(v) = &config.ytdl {
+        options.push(yt_path(v));
+    }
+
+    // Append extra options of matched site rule
+    options.extend_from_slice(config.mpv_args(&proto.url));
+
+    Ok(options)
+}
+
+/// Return cookies option
+fn cookies(cookies: &str) -> Result<Option<String>, Error> {
+    Ok(crate::plugins::play::cookies_path(cookies)?
+        .map(|cookies| format!("{PREFIX_COOKIES}{cookies}")))
+}
+
+/// Return profile option
+fn profile(profile: &str) -> String {
+    format!("{PREFIX_PROFILE}{profile}")
+}
+
+/// Return formats option
+fn formats(quality: Option<Quality>, v_codec: Option<VideoCodec>) -> Option<String> {
+    crate::plugins::play::format_sort(quality, v_codec)
+        .map(|formats| format!("{PREFIX_FORMATS}{formats}"))
+}
+
+/// Return v_title option
+fn v_title(v_title: &str) -> String {
+    format!("{PREFIX_V_TITLE}{v_title}")
+}
+
+/// Return subfile option
+fn subfile(subfile: &str) -> String {
+    format!("{PREFIX_SUBFILE}{subfile}")
+}
+
+/// Return startat option
+fn startat(startat: StartAt) -> String {
+    format!("{PREFIX_STARTAT}{startat}")
+}
+
+/// Return referrer option
+fn referrer(referrer: &str) -> String {
+    format!("{PREFIX_REFERRER}{referrer}")
+}
+
+/// Return yt_path option
+fn yt_path(yt_path: &str) -> String {
+    format!("{PREFIX_YT_PATH}{yt_path}")
+}
+
+/// Return ipc_server option
+pub fn ipc_server(ipc_server: &str) -> String {
+    format!("{PREFIX_IPC_SERVER}{ipc_server}")
+}
+
+#[test]
+fn test_profile_option() {
+    let p = profile("low-latency");
+    assert_eq!(p, format!("{PREFIX_PROFILE}low-latency"));
+}
+
+#[test]
+fn test_formats_option() {
+    // Only quality
+    let q = formats(Some(Quality::P720), None);
+    assert_eq!(q.unwrap(), format!("{PREFIX_FORMATS}res:720"));
+
+    // Only v_codec
+    let v = formats(None, Some(VideoCodec::Vp9));
+    assert_eq!(v.unwrap(), format!("{PREFIX_FORMATS}+vcodec:vp9"));
+
+    // Both quality and v_codec
+    let qv = formats(Some(Quality::P720), Some(VideoCodec::Vp9));
+    assert_eq!(qv.unwrap(), format!("{PREFIX_FORMATS}res:720,+vcodec:vp9"));
+}
+#[test]
+fn test_v_title_option() {
+    let t = v_title("Hello World!");
+    assert_eq!(t, format!("{PREFIX_V_TITLE}Hello World!"));
+}
+
+#[test]
+fn test_subfile_option() {
+    let s = subfile("http://example.com/en.ass");
+    assert_eq!(s, format!("{PREFIX_SUBFILE}http://example.com/en.ass"));
+}
+
+#[test]
+fn test_startat_option() {
+    let s = startat(StartAt::Seconds(233.0));
+    assert_eq!(s, format!("{PREFIX_STARTAT}233"));
+
+    let s = startat(StartAt::Percent(50.0));
+    assert_eq!(s, format!("{PREFIX_STARTAT}50%"));
+}
+
+#[test]
+fn test_referrer_option() {
+    let r = referrer("http://example.com/");
+    assert_eq!(r, format!("{PREFIX_REFERRER}http://example.com/"));
+}
+
+#[test]
+fn test_yt_path_option() {
+    let y = yt_path("/usr/bin/yt-dlp");
+    assert_eq!(y, format!("{PREFIX_YT_PATH}/usr/bin/yt-dlp"));
+}
+
+#[test]
+fn test_ipc_server_option() {
+    let i = ipc_server("/tmp/mpvsocket");
+    assert_eq!(i, format!("{PREFIX_IPC_SERVER}/tmp/mpvsocket"));
+}
+
+#[test]
+fn test_video_options() {
+    let video = Video {
+        url: "https://cdn.example.com/video.mp4".to_string(),
+        audio_url: Some("https://cdn.example.com/audio.m4a".to_string()),
+        headers: vec![("User-Agent".to_string(), "Mozilla/5.0".to_string())],
+        title: Some("Title".to_string()),
+        chapters: Vec::new(),
+    };
+
+    assert_eq!(
+        Mpv.video_options(&video).unwrap(),
+        [
+            NO_YTDL.to_string(),
+            format!("{PREFIX_AUDIO_FILE}https://cdn.example.com/audio.m4a"),
+            format!("{PREFIX_HTTP_HEADER}User-Agent: Mozilla/5.0"),
+            format!("{PREFIX_MEDIA_TITLE}Title"),
+        ]
+    );
+}
+
+#[test]
+fn test_playlist_options() {
+    let config = Config::default();
+    let proto = Protocol::parse(
+        "mpv-handler://play/aHR0cHM6Ly9leGFtcGxlLmNvbS8x/aHR0cHM6Ly9leGFtcGxlLmNvbS8y/?playlist_start=1&shuffle=true",
+        &config,
+    )
+    .unwrap();
+    assert_eq!(
+        Mpv.playlist_options(&proto),
+        [format!("{PREFIX_PLAYLIST_START}1"), SHUFFLE.to_string()]
+    );
+
+    let proto =
+        Protocol::parse("mpv-handler://play/aHR0cHM6Ly9leGFtcGxlLmNvbS8x", &config).unwrap();
+    assert!(Mpv.playlist_options(&proto).is_empty());
+}
@@ -0,0 +1,143 @@
+use crate::config::Config;
+use crate::error::Error;
+use crate::params::StartAt;
+use crate::players::Player;
+use crate::protocol::Protocol;
+use crate::resolve::Video;
+
+const PREFIX_V_TITLE: &str = "--meta-title=";
+const PREFIX_SUBFILE: &str = "--sub-file=";
+const PREFIX_STARTAT: &str = "--start-time=";
+const PREFIX_REFERRER: &str = "--http-referrer=";
+const PREFIX_AUDIO_FILE: &str = "--input-slave=";
+const PREFIX_USER_AGENT: &str = "--http-user-agent=";
+const RANDOM: &str = "--random";
+
+/// VLC media player
+///
+/// VLC can't open most video sites, the video URL is resolved by yt-dlp,
+/// so `cookies`, `quality` and `v_codec` are applied by yt-dlp.
+pub struct Vlc;
+
+impl Player for Vlc {
+    fn name(&self) -> &'static str {
+        "vlc"
+    }
+
+    fn binary(&self, config: &Config) -> Result<String, Error> {
+        match &config.vlc {
+            Some(v) => Ok(v.clone()),
+            None => crate::config::default_vlc(),
+        }
+    }
+
+    fn ytdl(&self) -> bool {
+        false
+    }
+
+    fn options(&self, proto: &Protocol, _config: &Config) -> Result<Vec<String>, Error> {
+        let mut options: Vec<String> = Vec::new();
+
+        // Append v_title option
+        if let Some(v) = &proto.v_title {
+            options.push(format!("{PREFIX_V_TITLE}{v}"));
+        }
+
+        // Append subfile option
+        if let Some(v) = &proto.subfile {
+            options.push(format!("{PREFIX_SUBFILE}{v}"));
+        }
+
+        // Append startat option, VLC doesn't support percent position
+        if let Some(StartAt::Seconds(v)) = proto.startat {
+            options.push(format!("{PREFIX_STARTAT}{v}"));
+        }
+
+        // Append referrer option
+        if let Some(v) = &proto.referrer {
+            options.push(format!("{PREFIX_REFERRER}{v}"));
+        }
+
+        Ok(options)
+    }
+
+    fn playlist_options(&self, proto: &Protocol) -> Vec<String> {
+        // VLC doesn't support start index of playlist
+        match proto.shuffle {
+            true => vec![RANDOM.to_string()],
+            false => Vec::new(),
+        }
+    }
+
+    fn video_options(&self, video: &Video) -> Result<Vec<String>, Error> {
+        let mut options: Vec<String> = Vec::new();
+
+        if let Some(v) = &video.audio_url {
+            options.push(format!("{PREFIX_AUDIO_FILE}{v}"));
+        }
+
+        // VLC only supports user agent of the HTTP headers
+        if let Some((_, v)) = video
+            .headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case("User-Agent"))
+        {
+            options.push(format!("{PREFIX_USER_AGENT}{v}"));
+        }
+
+        Ok(options)
+    }
+
+    fn item_options(&self, video: &Video) -> Result<Vec<String>, Error> {
+        // Item options are prefixed with `:` instead of `--`
+        let options = self.video_options(video)?;
+        Ok(options.iter().map(|v| v.replacen("--", ":", 1)).collect())
+    }
+}
+
+#[test]
+fn test_vlc_options() {
+    let config = Config::default();
+    let proto = Protocol::parse(
+        "mpv-handler://play/aHR0cHM6Ly9leGFtcGxlLmNvbS8/?v_title=VGl0bGU&startat=1:30&profile=low-latency",
+        &config,
+    )
+    .unwrap();
+
+    assert_eq!(
+        Vlc.options(&proto, &config).unwrap(),
+        ["--meta-title=Title", "--start-time=90"]
+    );
+
+    // Percent position is ignored
+    let proto = Protocol::parse(
+        "mpv-handler://play/aHR0cHM6Ly9leGFtcGxlLmNvbS8/?startat=50%",
+        &config,
+    )
+    .unwrap();
+    assert!(Vlc.options(&proto, &config).unwrap().is_empty());
+
+    let video = Video {
+        url: "https://example.com/v.mp4".to_string(),
+        audio_url: Some("https://example.com/a.m4a".to_string()),
+        headers: vec![
+            ("User-Agent".to_string(), "Mozilla/5.0".to_string()),
+            ("Accept".to_string(), "*/*".to_string()),
+        ],
+        ..Default::default()
+    };
+    assert_eq!(
+        Vlc.video_options(&video).unwrap(),
+        [
+            "--input-slave=https://example.com/a.m4a",
+            "--http-user-agent=Mozilla/5.0"
+        ]
+    );
+    assert_eq!(
+        Vlc.item_options(&video).unwrap(),
+        [
+            ":input-slave=https://example.com/a.m4a",
+            ":http-user-agent=Mozilla/5.0"
+        ]
+    );
+}
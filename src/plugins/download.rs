@@ -0,0 +1,159 @@
+use crate::config::Config;
+use crate::error::Error;
+use crate::protocol::Protocol;
+
+const PREFIX_COOKIES: &str = "--cookies=";
+const PREFIX_FORMATS: &str = "--format-sort=";
+const PREFIX_REFERRER: &str = "--referer=";
+const PREFIX_PATHS: &str = "--paths=";
+
+/// Execute yt-dlp to download video with given options
+pub fn exec(proto: &Protocol, config: &Config) -> Result<(), Error> {
+    let options: Vec<String> = options(proto, config)?;
+
+    // Ask user before downloading
+    crate::confirm::confirm(proto, config, &options)?;
+
+    // Print binaries and options list (in debug build)
+    if proto.scheme == crate::protocol::Schemes::MpvHandlerDebug || cfg!(debug_assertions) {
+        // Print binaries
+        eprintln!("Binaries:");
+        eprintln!("    {}", binary(config)?);
+
+        // Print options list
+        if !options.is_empty() {
+            eprintln!("Options:");
+            for option in &options {
+                eprintln!("    {}", option);
+            }
+        }
+    }
+
+    // Print video URL
+    for url in proto.urls() {
+        eprintln!("Downloading: {url}");
+    }
+
+    crate::log::info(
+        "argv",
+        crate::log::argv_fields(&binary(config)?, &options, &proto.urls()),
+    );
+
+    // Execute yt-dlp
+    let mut command = std::process::Command::new(binary(config)?);
+
+    command.args(&options).arg("--").args(proto.urls());
+
+    // Hide console window, set proxy and remove library paths environment variables
+    crate::plugins::apply_command(&mut command, proto, config);
+    crate::plugins::apply_stdout(&mut command);
+
+    match command.status() {
+        Ok(o) => {
+            crate::log::info("exited", serde_json::json!({ "code": o.code() }));
+
+            match o.code() {
+                Some(code) => match code {
+                    0 => Ok(()),
+                    _ => Err(Error::DownloaderExited(code as u8)),
+                },
+                None => Ok(()),
+            }
+        }
+        Err(e) => Err(Error::DownloaderRunFailed(e)),
+    }
+}
+
+/// Returns yt-dlp binary path
+pub fn binary(config: &Config) -> Result<String, Error> {
+    match &config.ytdl {
+        Some(v) => Ok(v.clone()),
+        None => crate::config::default_ytdl(),
+    }
+}
+
+/// Returns yt-dlp options of given protocol
+pub fn options(proto: &Protocol, config: &Config) -> Result<Vec<String>, Error> {
+    let mut options: Vec<String> = video_options(proto)?;
+
+    // Append download directory option
+    if let Some(v) = crate::config::download_dir(config) {
+        options.push(paths(&v));
+    }
+
+    Ok(options)
+}
+
+/// Returns yt-dlp options to select the video of given protocol
+///
+/// Shared by downloading and resolving video URL
+pub fn video_options(proto: &Protocol) -> Result<Vec<String>, Error> {
+    let mut options: Vec<String> = Vec::new();
+
+    // Append cookies option
+    if let Some(v) = proto.cookies
+        && let Some(v) = crate::plugins::play::cookies_path(v)?
+    {
+        options.push(cookies(&v));
+    }
+
+    // Append formats option
+    if (proto.quality.is_some() || proto.v_codec.is_some())
+        && let Some(v) = crate::plugins::play::format_sort(proto.quality, proto.v_codec)
+    {
+        options.push(formats(&v));
+    }
+
+    // Append referrer option
+    if let Some(v) = &proto.referrer {
+        options.push(referrer(v));
+    }
+
+    Ok(options)
+}
+
+/// Return cookies option
+fn cookies(cookies: &str) -> String {
+    format!("{PREFIX_COOKIES}{cookies}")
+}
+
+/// Return formats option
+fn formats(formats: &str) -> String {
+    format!("{PREFIX_FORMATS}{formats}")
+}
+
+/// Return referrer option
+fn referrer(referrer: &str) -> String {
+    format!("{PREFIX_REFERRER}{referrer}")
+}
+
+/// Return paths option
+fn paths(paths: &str) -> String {
+    format!("{PREFIX_PATHS}{paths}")
+}
+
+#[test]
+fn test_cookies_option() {
+    let c = cookies("/tmp/www.youtube.com.txt");
+    assert_eq!(c, format!("{PREFIX_COOKIES}/tmp/www.youtube.com.txt"));
+}
+
+#[test]
+fn test_formats_option() {
+    use crate::params::{Quality, VideoCodec};
+
+    let f = crate::plugins::play::format_sort(Some(Quality::P720), Some(VideoCodec::Vp9)).unwrap();
+    assert_eq!(formats(&f), format!("{PREFIX_FORMATS}res:720,+vcodec:vp9"));
+}
+
+#[test]
+fn test_referrer_option() {
+    let r = referrer("http://example.com/");
+    assert_eq!(r, format!("{PREFIX_REFERRER}http://example.com/"));
+}
+
+#[test]
+fn test_paths_option() {
+    let p = paths("/home/user/Downloads");
+    assert_eq!(p, format!("{PREFIX_PATHS}/home/user/Downloads"));
+}
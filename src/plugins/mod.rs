@@ -1,6 +1,123 @@
+use crate::config::Config;
+use crate::error::Error;
+use crate::protocol::Protocol;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+pub mod download;
 pub mod play;
+pub mod queue;
+
+/// Redirect stdout of player and downloader to stderr, set by JSON output mode
+static REDIRECT_STDOUT: AtomicBool = AtomicBool::new(false);
 
 #[derive(Debug, PartialEq)]
 pub enum Plugins {
     Play,
+    Queue,
+    Download,
+}
+
+impl Plugins {
+    /// Returns plugin by name
+    pub fn from_name(name: &str) -> Option<Plugins> {
+        match name {
+            "play" => Some(Plugins::Play),
+            "queue" => Some(Plugins::Queue),
+            "download" => Some(Plugins::Download),
+            _ => None,
+        }
+    }
+
+    /// Returns the name of plugin
+    pub fn name(&self) -> &'static str {
+        match self {
+            Plugins::Play => "play",
+            Plugins::Queue => "queue",
+            Plugins::Download => "download",
+        }
+    }
+}
+
+/// Call plugin of `proto`, and record playback history
+pub fn exec(proto: &Protocol, config: &Config) -> Result<(), Error> {
+    let result = match proto.plugin {
+        Plugins::Play => crate::plugins::play::exec(proto, config),
+        Plugins::Queue => crate::plugins::queue::exec(proto, config),
+        Plugins::Download => crate::plugins::download::exec(proto, config),
+    };
+
+    // Record the video played by player, even if player exited by error
+    if proto.plugin == Plugins::Play {
+        match &result {
+            Ok(_) => crate::history::record(proto, config, 0),
+            Err(Error::PlayerExited(code)) => crate::history::record(proto, config, *code),
+            Err(_) => {}
+        }
+    }
+
+    result
+}
+
+/// Returns environment variable changes of player and downloader
+///
+/// `Some(value)` sets the variable, and `None` removes it
+pub fn envs(proto: &Protocol, config: &Config) -> Vec<(&'static str, Option<String>)> {
+    let mut envs: Vec<(&'static str, Option<String>)> = Vec::new();
+
+    // Set HTTP(S) proxy environment variables
+    if let Some(proxy) = config.proxy(&proto.url) {
+        for k in ["http_proxy", "HTTP_PROXY", "https_proxy", "HTTPS_PROXY"] {
+            envs.push((k, Some(proxy.to_string())));
+        }
+    }
+
+    // Fix some browsers to overwrite "LD_LIBRARY_PATH" on Linux
+    // It will be broken mpv player
+    // mpv: symbol lookup error: mpv: undefined symbol: vkCreateWaylandSurfaceKHR
+    #[cfg(unix)]
+    envs.push(("LD_LIBRARY_PATH", None));
+
+    // Fix Vivaldi to overwrite "LD_PRELOAD" on Linux
+    // https://github.com/akiirui/mpv-handler/issues/78
+    #[cfg(unix)]
+    envs.push(("LD_PRELOAD", None));
+
+    envs
+}
+
+/// Apply environment variable changes to command
+pub fn apply_envs(command: &mut std::process::Command, envs: &[(&str, Option<String>)]) {
+    for (k, v) in envs {
+        match v {
+            Some(v) => command.env(k, v),
+            None => command.env_remove(k),
+        };
+    }
+}
+
+/// Prepare command of player, downloader and yt-dlp
+///
+/// Hide console window on Windows if not in debug mode, and apply `envs()`.
+pub fn apply_command(command: &mut std::process::Command, proto: &Protocol, config: &Config) {
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        if proto.scheme == crate::protocol::Schemes::MpvHandler && !cfg!(debug_assertions) {
+            command.creation_flags(0x08000000);
+        }
+    }
+
+    apply_envs(command, &envs(proto, config));
+}
+
+/// Keep stdout for JSON output, the stdout of player and downloader is redirected to stderr
+pub fn redirect_stdout() {
+    REDIRECT_STDOUT.store(true, Ordering::Relaxed);
+}
+
+/// Apply stdout redirection to command
+pub fn apply_stdout(command: &mut std::process::Command) {
+    if REDIRECT_STDOUT.load(Ordering::Relaxed) {
+        command.stdout(std::io::stderr());
+    }
 }
@@ -1,189 +1,200 @@
 use crate::config::Config;
 use crate::error::Error;
+use crate::ipc::{Client, LoadFlags};
+use crate::params::{Quality, VideoCodec};
 use crate::protocol::Protocol;
 
-const PREFIX_COOKIES: &str = "--ytdl-raw-options-append=cookies=";
-const PREFIX_PROFILE: &str = "--profile=";
-const PREFIX_FORMATS: &str = "--ytdl-raw-options-append=format-sort=";
-const PREFIX_V_TITLE: &str = "--title=";
-const PREFIX_SUBFILE: &str = "--sub-file=";
-const PREFIX_STARTAT: &str = "--start=";
-const PREFIX_REFERRER: &str = "--referrer=";
-const PREFIX_YT_PATH: &str = "--script-opts=ytdl_hook-ytdl_path=";
-
 /// Execute player with given options
 pub fn exec(proto: &Protocol, config: &Config) -> Result<(), Error> {
-    let mut options: Vec<&str> = Vec::new();
-    let option_cookies: String;
-    let option_profile: String;
-    let option_formats: String;
-    let option_v_title: String;
-    let option_subfile: String;
-    let option_startat: String;
-    let option_yt_path: String;
-    let option_referrer: String;
-
-    // Append cookies option
-    if let Some(v) = proto.cookies {
-        if let Some(v) = cookies(v) {
-            option_cookies = v;
-            options.push(&option_cookies);
-        }
-    }
+    exec_with(proto, config, LoadFlags::Replace)
+}
 
-    // Append profile option
-    if let Some(v) = proto.profile {
-        option_profile = profile(v);
-        options.push(&option_profile);
-    }
+/// Execute player with given options
+///
+/// If a running player is connected by JSON IPC, load video URLs by `flags`.
+/// The player is chosen by `Config.player` (or site rule), if the player can't
+/// open the URL of video site, the video URL is resolved by yt-dlp first.
+///
+/// A playlist is passed to the player as multiple URLs, it isn't resolved in
+/// resolve mode. For the player without yt-dlp, each video URL is resolved and
+/// followed by its item options (e.g. separate audio URL).
+pub fn exec_with(proto: &Protocol, config: &Config, flags: LoadFlags) -> Result<(), Error> {
+    let player = crate::players::player(config, &proto.url)?;
+    let mut options: Vec<String> = player.options(proto, config)?;
+    let mut client: Option<Client> = None;
+    let mut urls: Vec<String> = proto.urls().iter().map(|v| v.to_string()).collect();
+
+    // Ask user before playing
+    crate::confirm::confirm(proto, config, &options)?;
+
+    // Connect to running mpv player, or start a new player with IPC server
+    if player.name() == "mpv"
+        && let Some(v) = &config.ipc_server
+    {
+        client = Client::connect(v);
 
-    // Append formats option
-    if proto.quality.is_some() || proto.v_codec.is_some() {
-        if let Some(v) = formats(proto.quality, proto.v_codec) {
-            option_formats = v;
-            options.push(&option_formats);
+        if client.is_none() {
+            options.push(crate::players::mpv::ipc_server(v));
         }
     }
 
-    // Append v_title option
-    if let Some(v) = &proto.v_title {
-        option_v_title = v_title(v);
-        options.push(&option_v_title);
-    }
-
-    // Append subfile option
-    if let Some(v) = &proto.subfile {
-        option_subfile = subfile(v);
-        options.push(&option_subfile);
-    }
-
-    // Append startat option
-    if let Some(v) = &proto.startat {
-        option_startat = startat(v);
-        options.push(&option_startat);
-    }
-
-    // Append referrer option
-    if let Some(v) = &proto.referrer {
-        option_referrer = referrer(v);
-        options.push(&option_referrer);
-    }
-
-    // Set custom ytdl execute file path
-    if let Some(v) = &config.ytdl {
-        option_yt_path = yt_path(v);
-        options.push(&option_yt_path);
+    // Start index and shuffle of playlist for a new player
+    if client.is_none() {
+        options.extend(player.playlist_options(proto));
     }
 
     // Print binaries and options list (in debug build)
-    if &proto.scheme == &crate::protocol::Schemes::MpvHandlerDebug || cfg!(debug_assertions) {
+    if proto.scheme == crate::protocol::Schemes::MpvHandlerDebug || cfg!(debug_assertions) {
         // Print binaries
-        println!("Binaries:");
-
-        if let Some(v) = &config.mpv {
-            println!("    {}", v);
-        } else {
-            println!("    {}", crate::config::default_mpv()?);
-        }
+        eprintln!("Binaries:");
+        eprintln!("    {}", player.binary(config)?);
 
         if let Some(v) = &config.ytdl {
-            println!("    {}", v);
+            eprintln!("    {}", v);
         }
 
         // Print options list
         if !options.is_empty() {
-            println!("Options:");
+            eprintln!("Options:");
             for option in &options {
-                println!("    {}", option);
+                eprintln!("    {}", option);
             }
         }
     }
 
-    // Print video URL
-    println!("Playing: {}", proto.url);
+    // Print video URLs
+    for url in &urls {
+        eprintln!("Playing: {url}");
+    }
 
-    // Execute mpv player
-    let mut command;
+    // Resolve video URL by yt-dlp, if the player can't do it or in resolve mode
+    if urls.len() == 1 && (!player.ytdl() || config.resolve) {
+        let video = crate::resolve::resolve(proto, &urls[0], config)?;
 
-    if let Some(v) = &config.mpv {
-        command = std::process::Command::new(v);
-    } else {
-        command = std::process::Command::new(crate::config::default_mpv()?);
-    }
+        options.extend(player.video_options(&video)?);
+        urls[0] = video.url;
+    } else if !player.ytdl() {
+        let mut items: Vec<String> = Vec::new();
 
-    command.args(&options).arg("--").arg(&proto.url);
+        for url in &urls {
+            let video = crate::resolve::resolve(proto, url, config)?;
+            let options = player.item_options(&video)?;
 
-    // Hide console window on Windows if not in debug mode
-    #[cfg(windows)]
-    {
-        use std::os::windows::process::CommandExt;
-        if &proto.scheme == &crate::protocol::Schemes::MpvHandler && !cfg!(debug_assertions) {
-            command.creation_flags(0x08000000);
+            items.push(video.url);
+            items.extend(options);
         }
+
+        urls = items;
     }
 
-    // Set HTTP(S) proxy environment variables
-    if let Some(proxy) = &config.proxy {
-        command.env("http_proxy", proxy);
-        command.env("HTTP_PROXY", proxy);
-        command.env("https_proxy", proxy);
-        command.env("HTTPS_PROXY", proxy);
+    // Load video URLs in running mpv player, the rest of playlist is appended
+    if let Some(mut client) = client {
+        crate::log::info(
+            "ipc",
+            serde_json::json!({
+                "ipc_server": config.ipc_server,
+                "flags": flags.as_str(),
+                "urls": urls.iter().map(|v| crate::log::redact(v)).collect::<Vec<String>>(),
+            }),
+        );
+
+        client.loadfile(&urls[0], flags, &options)?;
+        for url in &urls[1..] {
+            client.loadfile(url, LoadFlags::AppendPlay, &options)?;
+        }
+
+        // Remove chapters file after player loaded the video (or IPC timed out)
+        let chapters = crate::resolve::chapters_path();
+        if chapters.exists() {
+            let _ = client.wait_event("file-loaded");
+            let _ = std::fs::remove_file(chapters);
+        }
+
+        return Ok(());
     }
 
-    // Fix some browsers to overwrite "LD_LIBRARY_PATH" on Linux
-    // It will be broken mpv player
-    // mpv: symbol lookup error: mpv: undefined symbol: vkCreateWaylandSurfaceKHR
-    #[cfg(unix)]
-    command.env_remove("LD_LIBRARY_PATH");
+    crate::log::info(
+        "argv",
+        crate::log::argv_fields(&player.binary(config)?, &options, &urls),
+    );
 
-    // Fix Vivaldi to overwrite "LD_PRELOAD" on Linux
-    // https://github.com/akiirui/mpv-handler/issues/78
-    #[cfg(unix)]
-    command.env_remove("LD_PRELOAD");
-
-    match command.status() {
-        Ok(o) => match o.code() {
-            Some(code) => match code {
-                0 => Ok(()),
-                _ => Err(Error::PlayerExited(code as u8)),
-            },
-            None => Ok(()),
-        },
+    // Execute player
+    let mut command = std::process::Command::new(player.binary(config)?);
+
+    command.args(&options).arg("--").args(&urls);
+
+    // Hide console window, set proxy and remove library paths environment variables
+    crate::plugins::apply_command(&mut command, proto, config);
+    crate::plugins::apply_stdout(&mut command);
+
+    let status = command.status();
+
+    // Remove chapters file after player exited
+    let _ = std::fs::remove_file(crate::resolve::chapters_path());
+
+    match status {
+        Ok(o) => {
+            crate::log::info("exited", serde_json::json!({ "code": o.code() }));
+
+            match o.code() {
+                Some(code) => match code {
+                    0 => Ok(()),
+                    _ => Err(Error::PlayerExited(code as u8)),
+                },
+                None => Ok(()),
+            }
+        }
         Err(e) => Err(Error::PlayerRunFailed(e)),
     }
 }
 
-/// Return cookies option
-fn cookies(cookies: &str) -> Option<String> {
+/// Return cookies file path in config directory
+pub fn cookies_path(cookies: &str) -> Result<Option<String>, Error> {
     match crate::config::get_config_dir() {
         Some(mut p) => {
             p.push("cookies");
-            p.push(cookies);
-
-            if p.exists() {
-                let cookies = p.display();
-                return Some(format!("{PREFIX_COOKIES}{cookies}"));
-            } else {
-                eprintln!("Cookies file not found \"{}\"", p.display());
-                return None;
-            }
+            cookies_path_in(&p, cookies)
         }
-        None => None,
+        None => Ok(None),
     }
 }
 
-/// Return profile option
-fn profile(profile: &str) -> String {
-    format!("{PREFIX_PROFILE}{profile}")
+/// Return cookies file path in the given cookies directory
+///
+/// Only plain file names are accepted, and the canonical path of cookies file
+/// must stay inside the cookies directory (e.g. reject symlinks to outside).
+fn cookies_path_in(dir: &std::path::Path, cookies: &str) -> Result<Option<String>, Error> {
+    let name = std::path::Path::new(cookies);
+
+    if cookies.contains(['/', '\\']) || name.file_name() != Some(name.as_os_str()) {
+        return Err(Error::IncorrectCookies(cookies.to_string()));
+    }
+
+    let p = dir.join(name);
+
+    if !p.exists() {
+        eprintln!("Cookies file not found \"{}\"", p.display());
+        crate::log::warn(
+            "cookies",
+            serde_json::json!({ "message": "Cookies file not found", "path": p }),
+        );
+        return Ok(None);
+    }
+
+    match (p.canonicalize(), dir.canonicalize()) {
+        (Ok(file), Ok(dir)) if file.starts_with(&dir) && file.is_file() => {
+            Ok(Some(file.display().to_string()))
+        }
+        _ => Err(Error::IncorrectCookies(cookies.to_string())),
+    }
 }
 
-/// Return formats option
-fn formats(quality: Option<&str>, v_codec: Option<&str>) -> Option<String> {
+/// Return yt-dlp format sorting of quality and v_codec
+pub fn format_sort(quality: Option<Quality>, v_codec: Option<VideoCodec>) -> Option<String> {
     let mut f: Vec<String> = Vec::new();
-    let formats: String;
 
     if let Some(v) = quality {
-        let i: String = v.matches(char::is_numeric).collect();
+        let i = v.height();
         f.push(format!("res:{i}"));
     }
 
@@ -191,82 +202,50 @@ fn formats(quality: Option<&str>, v_codec: Option<&str>) -> Option<String> {
         f.push(format!("+vcodec:{v}"))
     }
 
-    formats = f.join(",");
-
-    Some(format!("{PREFIX_FORMATS}{formats}"))
-}
-
-/// Return v_title option
-fn v_title(v_title: &str) -> String {
-    format!("{PREFIX_V_TITLE}{v_title}")
-}
-
-/// Return subfile option
-fn subfile(subfile: &str) -> String {
-    format!("{PREFIX_SUBFILE}{subfile}")
-}
-
-/// Return startat option
-fn startat(startat: &str) -> String {
-    format!("{PREFIX_STARTAT}{startat}")
-}
-
-/// Return referrer option
-fn referrer(referrer: &str) -> String {
-    format!("{PREFIX_REFERRER}{referrer}")
-}
-
-/// Return yt_path option
-fn yt_path(yt_path: &str) -> String {
-    format!("{PREFIX_YT_PATH}{yt_path}")
-}
-
-#[test]
-fn test_profile_option() {
-    let p = profile("low-latency");
-    assert_eq!(p, format!("{PREFIX_PROFILE}low-latency"));
-}
-
-#[test]
-fn test_formats_option() {
-    // Only quality
-    let q = formats(Some("720p"), None);
-    assert_eq!(q.unwrap(), format!("{PREFIX_FORMATS}res:720"));
-
-    // Only v_codec
-    let v = formats(None, Some("vp9"));
-    assert_eq!(v.unwrap(), format!("{PREFIX_FORMATS}+vcodec:vp9"));
-
-    // Both quality and v_codec
-    let qv = formats(Some("720p"), Some("vp9"));
-    assert_eq!(qv.unwrap(), format!("{PREFIX_FORMATS}res:720,+vcodec:vp9"));
-}
-#[test]
-fn test_v_title_option() {
-    let t = v_title("Hello World!");
-    assert_eq!(t, format!("{PREFIX_V_TITLE}Hello World!"));
-}
-
-#[test]
-fn test_subfile_option() {
-    let s = subfile("http://example.com/en.ass");
-    assert_eq!(s, format!("{PREFIX_SUBFILE}http://example.com/en.ass"));
+    Some(f.join(","))
 }
 
 #[test]
-fn test_startat_option() {
-    let s = startat("233");
-    assert_eq!(s, format!("{PREFIX_STARTAT}233"));
-}
+fn test_cookies_path() {
+    let dir = crate::testing::temp_path("cookies");
+    let cookies = dir.join("cookies");
+    std::fs::create_dir_all(&cookies).unwrap();
+    std::fs::write(cookies.join("www.youtube.com.txt"), "").unwrap();
+    std::fs::write(dir.join("secret.txt"), "").unwrap();
+
+    // Plain file name
+    let c = cookies_path_in(&cookies, "www.youtube.com.txt").unwrap();
+    let expected = cookies.canonicalize().unwrap().join("www.youtube.com.txt");
+    assert_eq!(c, Some(expected.display().to_string()));
+
+    // Not found
+    assert_eq!(cookies_path_in(&cookies, "not-found.txt").unwrap(), None);
+
+    // Path traversal and absolute path
+    for name in [
+        "../secret.txt",
+        "..",
+        ".",
+        "",
+        "/etc/passwd",
+        "a/b.txt",
+        "..\\secret.txt",
+    ] {
+        match cookies_path_in(&cookies, name) {
+            Err(Error::IncorrectCookies(v)) => assert_eq!(v, name),
+            v => panic!("unexpected result of {name:?}: {v:?}"),
+        }
+    }
 
-#[test]
-fn test_referrer_option() {
-    let r = referrer("http://example.com/");
-    assert_eq!(r, format!("{PREFIX_REFERRER}http://example.com/"));
-}
+    // Symlink to outside of cookies directory
+    #[cfg(unix)]
+    {
+        std::os::unix::fs::symlink(dir.join("secret.txt"), cookies.join("link.txt")).unwrap();
+        assert!(matches!(
+            cookies_path_in(&cookies, "link.txt"),
+            Err(Error::IncorrectCookies(_))
+        ));
+    }
 
-#[test]
-fn test_yt_path_option() {
-    let y = yt_path("/usr/bin/yt-dlp");
-    assert_eq!(y, format!("{PREFIX_YT_PATH}/usr/bin/yt-dlp"));
+    std::fs::remove_dir_all(&dir).unwrap();
 }
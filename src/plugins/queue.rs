@@ -0,0 +1,28 @@
+use crate::config::Config;
+use crate::error::Error;
+use crate::ipc::LoadFlags;
+use crate::protocol::Protocol;
+
+/// Append video to the playlist of running player
+///
+/// If no player is running, execute a new player with given options.
+/// `Config.ipc_server` is required to find the running player.
+pub fn exec(proto: &Protocol, config: &Config) -> Result<(), Error> {
+    if config.ipc_server.is_none() {
+        return Err(Error::MissingConfig("ipc_server".to_string()));
+    }
+
+    crate::plugins::play::exec_with(proto, config, LoadFlags::AppendPlay)
+}
+
+#[test]
+fn test_queue_without_ipc_server() {
+    let config = Config::default();
+    let proto =
+        Protocol::parse("mpv-handler://queue/aHR0cHM6Ly9leGFtcGxlLmNvbS8", &config).unwrap();
+
+    assert!(matches!(
+        exec(&proto, &config),
+        Err(Error::MissingConfig(v)) if v == "ipc_server"
+    ));
+}
@@ -0,0 +1,292 @@
+use crate::config::Config;
+use crate::error::Error;
+use crate::protocol::Protocol;
+use serde_json::Value;
+use std::path::PathBuf;
+
+/// Video resolved by yt-dlp
+///
+/// - `url`: the URL of video (or video with audio)
+/// - `audio_url`: the URL of separate audio
+/// - `headers`: HTTP headers required by the URLs
+/// - `title`: video title
+/// - `chapters`: video chapters
+#[derive(Debug, Default, PartialEq)]
+pub struct Video {
+    pub url: String,
+    pub audio_url: Option<String>,
+    pub headers: Vec<(String, String)>,
+    pub title: Option<String>,
+    pub chapters: Vec<Chapter>,
+}
+
+/// Video chapter, the time is in seconds
+#[derive(Debug, PartialEq)]
+pub struct Chapter {
+    pub start: f64,
+    pub end: f64,
+    pub title: String,
+}
+
+/// Resolve the video URL (one of `proto.urls()`) by yt-dlp (`yt-dlp -J`)
+///
+/// The video is selected by `cookies`, `quality`, `v_codec` and `referrer` of `proto`.
+pub fn resolve(proto: &Protocol, url: &str, config: &Config) -> Result<Video, Error> {
+    let binary = crate::plugins::download::binary(config)?;
+    let mut options: Vec<String> = vec!["-J".to_string(), "--no-playlist".to_string()];
+    options.extend(crate::plugins::download::video_options(proto)?);
+
+    crate::log::info(
+        "resolve",
+        crate::log::argv_fields(&binary, &options, &[url]),
+    );
+
+    let mut command = std::process::Command::new(&binary);
+
+    command
+        .args(&options)
+        .arg("--")
+        .arg(url)
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped());
+
+    // Hide console window, set proxy and remove library paths environment variables
+    crate::plugins::apply_command(&mut command, proto, config);
+
+    let output = command.output().map_err(Error::ResolveRunFailed)?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let message = stderr
+            .lines()
+            .rev()
+            .find(|v| !v.trim().is_empty())
+            .unwrap_or("yt-dlp exited by error");
+        return Err(Error::ResolveFailed(message.trim().to_string()));
+    }
+
+    parse(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Parse the JSON output of `yt-dlp -J`
+///
+/// The resolved URLs are checked by the safe protocols, as the video URL of link.
+pub fn parse(data: &str) -> Result<Video, Error> {
+    let info: Value = serde_json::from_str(data)?;
+    let url = |v: &Value| v["url"].as_str().map(str::to_string);
+
+    // Separate video and audio formats, e.g. `bestvideo+bestaudio`
+    let formats: Vec<&Value> = match info["requested_formats"].as_array() {
+        Some(v) => v.iter().filter(|v| url(v).is_some()).collect(),
+        None => Vec::new(),
+    };
+
+    let (format, audio_url) = match formats.as_slice() {
+        [video, audio, ..] => (*video, url(audio)),
+        [video] => (*video, None),
+        [] => (&info, None),
+    };
+
+    let mut video = Video {
+        url: url(format).ok_or(Error::ResolveFailed("no video URL".to_string()))?,
+        audio_url,
+        headers: headers(format),
+        title: info["title"].as_str().map(str::to_string),
+        chapters: chapters(&info),
+    };
+
+    // Some extractors only set the headers of whole video
+    if video.headers.is_empty() {
+        video.headers = headers(&info);
+    }
+
+    crate::protocol::check_protocol(&video.url)?;
+    if let Some(v) = &video.audio_url {
+        crate::protocol::check_protocol(v)?;
+    }
+
+    Ok(video)
+}
+
+/// Returns `http_headers` of format, the headers with line breaks are skipped
+fn headers(format: &Value) -> Vec<(String, String)> {
+    match format["http_headers"].as_object() {
+        Some(v) => v
+            .iter()
+            .filter_map(|(k, v)| Some((k.clone(), v.as_str()?.to_string())))
+            .filter(|(k, v)| !format!("{k}{v}").contains(['\r', '\n']))
+            .collect(),
+        None => Vec::new(),
+    }
+}
+
+/// Returns `chapters` of video
+fn chapters(info: &Value) -> Vec<Chapter> {
+    match info["chapters"].as_array() {
+        Some(v) => v
+            .iter()
+            .filter_map(|v| {
+                Some(Chapter {
+                    start: v["start_time"].as_f64()?,
+                    end: v["end_time"].as_f64()?,
+                    title: v["title"].as_str().unwrap_or_default().to_string(),
+                })
+            })
+            .collect(),
+        None => Vec::new(),
+    }
+}
+
+/// Returns chapters file path of current process
+pub fn chapters_path() -> PathBuf {
+    std::env::temp_dir().join(format!("mpv-handler-chapters-{}.txt", std::process::id()))
+}
+
+/// Write chapters to FFmetadata file, which can be loaded by mpv `--chapters-file`
+///
+/// Returns `None` if the video has no chapters
+pub fn write_chapters(video: &Video) -> Result<Option<PathBuf>, Error> {
+    if video.chapters.is_empty() {
+        return Ok(None);
+    }
+
+    let mut data = String::from(";FFMETADATA1\n");
+
+    for chapter in &video.chapters {
+        data.push_str("[CHAPTER]\nTIMEBASE=1/1000\n");
+        data.push_str(&format!("START={}\n", (chapter.start * 1000.0) as u64));
+        data.push_str(&format!("END={}\n", (chapter.end * 1000.0) as u64));
+        data.push_str(&format!("title={}\n", escape(&chapter.title)));
+    }
+
+    let path = chapters_path();
+    std::fs::write(&path, data)?;
+
+    Ok(Some(path))
+}
+
+/// Escape special characters of FFmetadata value
+fn escape(value: &str) -> String {
+    let mut escaped = String::new();
+
+    for c in value.chars() {
+        if matches!(c, '=' | ';' | '#' | '\\' | '\n') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+
+    escaped
+}
+
+#[test]
+fn test_parse() {
+    let data = r#"{
+        "title": "Title",
+        "requested_formats": [
+            { "url": "https://cdn.example.com/video.mp4" },
+            { "url": "https://cdn.example.com/audio.m4a" }
+        ]
+    }"#;
+    assert_eq!(
+        parse(data).unwrap(),
+        Video {
+            url: "https://cdn.example.com/video.mp4".to_string(),
+            audio_url: Some("https://cdn.example.com/audio.m4a".to_string()),
+            title: Some("Title".to_string()),
+            ..Default::default()
+        }
+    );
+
+    let data = r#"{ "title": "Title", "url": "https://cdn.example.com/v.m3u8" }"#;
+    assert_eq!(
+        parse(data).unwrap(),
+        Video {
+            url: "https://cdn.example.com/v.m3u8".to_string(),
+            title: Some("Title".to_string()),
+            ..Default::default()
+        }
+    );
+
+    assert!(matches!(
+        parse(r#"{ "title": "Title" }"#),
+        Err(Error::ResolveFailed(_))
+    ));
+    assert!(matches!(parse("ERROR"), Err(Error::FromJsonError(_))));
+    assert!(matches!(
+        parse(r#"{ "url": "file:///etc/passwd" }"#),
+        Err(Error::DangerousVideoProtocol(_))
+    ));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_resolve() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = crate::testing::temp_path("resolve");
+    let ytdl = dir.join("yt-dlp");
+    std::fs::create_dir_all(&dir).unwrap();
+
+    // Fake yt-dlp prints canned JSON, and records its arguments
+    let script = format!(
+        r#"#!/bin/sh
+echo "$@" > '{}'
+cat <<'EOF'
+{{
+    "title": "Title",
+    "requested_formats": [
+        {{ "url": "https://cdn.example.com/video.mp4", "http_headers": {{ "User-Agent": "Mozilla/5.0" }} }},
+        {{ "url": "https://cdn.example.com/audio.m4a" }}
+    ],
+    "chapters": [
+        {{ "start_time": 0.0, "end_time": 90.5, "title": "Intro; A=B" }},
+        {{ "start_time": 90.5, "end_time": 180.0, "title": "Outro" }}
+    ]
+}}
+EOF
+"#,
+        dir.join("args").display()
+    );
+    std::fs::write(&ytdl, script).unwrap();
+    std::fs::set_permissions(&ytdl, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    let config: Config = toml::from_str(&format!("ytdl = \"{}\"", ytdl.display())).unwrap();
+    let link = "mpv-handler://play/aHR0cHM6Ly93d3cueW91dHViZS5jb20vd2F0Y2g_dj1HZ2tuMmY1ZS1JVQ/?quality=720p";
+    let proto = Protocol::parse(link, &config).unwrap();
+
+    let video = resolve(&proto, &proto.url, &config).unwrap();
+    assert_eq!(video.url, "https://cdn.example.com/video.mp4");
+    assert_eq!(
+        video.audio_url,
+        Some("https://cdn.example.com/audio.m4a".to_string())
+    );
+    assert_eq!(
+        video.headers,
+        [("User-Agent".to_string(), "Mozilla/5.0".to_string())]
+    );
+    assert_eq!(video.title, Some("Title".to_string()));
+    assert_eq!(video.chapters.len(), 2);
+    assert_eq!(
+        std::fs::read_to_string(dir.join("args")).unwrap(),
+        "-J --no-playlist --format-sort=res:720 -- https://www.youtube.com/watch?v=Ggkn2f5e-IU\n"
+    );
+
+    // Chapters file
+    let path = write_chapters(&video).unwrap().unwrap();
+    let data = std::fs::read_to_string(&path).unwrap();
+    assert!(data.starts_with(";FFMETADATA1\n[CHAPTER]\nTIMEBASE=1/1000\nSTART=0\nEND=90500\n"));
+    assert!(data.contains("title=Intro\\; A\\=B\n"));
+    std::fs::remove_file(&path).unwrap();
+
+    // yt-dlp failed
+    let script = "#!/bin/sh\necho 'ERROR: [youtube] Video unavailable' >&2\nexit 1\n";
+    std::fs::write(&ytdl, script).unwrap();
+    match resolve(&proto, &proto.url, &config) {
+        Err(Error::ResolveFailed(v)) => assert_eq!(v, "ERROR: [youtube] Video unavailable"),
+        v => panic!("unexpected result: {v:?}"),
+    }
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
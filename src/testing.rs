@@ -0,0 +1,13 @@
+//! Helpers shared by unit tests
+
+use std::path::PathBuf;
+
+/// Convert string slices to owned strings
+pub fn strings(v: &[&str]) -> Vec<String> {
+    v.iter().map(|v| v.to_string()).collect()
+}
+
+/// Returns path `mpv-handler-{name}-{pid}` in the temporary directory
+pub fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("mpv-handler-{}-{}", name, std::process::id()))
+}